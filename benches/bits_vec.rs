@@ -2,7 +2,7 @@
 extern crate test;
 extern crate nucleic_acid;
 
-use nucleic_acid::BitsVec;
+use nucleic_acid::{BitsVec, BitsVecBuilder};
 use test::Bencher;
 
 #[bench]
@@ -59,6 +59,30 @@ fn bench_22_bits_vec_push_1000_ints(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_22_bits_vec_build_1000_ints_with_push(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec = BitsVec::with_capacity(22, 1000);
+        for _ in 0..1000 {
+            vec.push(100);
+        }
+
+        vec
+    });
+}
+
+#[bench]
+fn bench_22_bits_vec_build_1000_ints_with_builder(b: &mut Bencher) {
+    b.iter(|| {
+        let mut builder = BitsVecBuilder::with_capacity(22, 1000);
+        for _ in 0..1000 {
+            builder.push(100);
+        }
+
+        builder.build()
+    });
+}
+
 #[bench]
 fn bench_22_bits_vec_get_1000_ints(b: &mut Bencher) {
     let vec = BitsVec::with_elements(22, 1000, 100);
@@ -69,6 +93,16 @@ fn bench_22_bits_vec_get_1000_ints(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_22_bits_vec_get_unchecked_1000_ints(b: &mut Bencher) {
+    let vec = BitsVec::with_elements(22, 1000, 100);
+    b.iter(|| {
+        for i in 0..1000 {
+            unsafe { vec.get_unchecked(i); }
+        }
+    });
+}
+
 #[bench]
 fn bench_22_bits_vec_set_1000_ints(b: &mut Bencher) {
     let mut vec = BitsVec::with_elements(22, 1000, 100);
@@ -143,6 +177,16 @@ fn bench_63_bits_vec_get_1000_ints(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_63_bits_vec_get_unchecked_1000_ints(b: &mut Bencher) {
+    let vec = BitsVec::with_elements(63, 1000, 100);
+    b.iter(|| {
+        for i in 0..1000 {
+            unsafe { vec.get_unchecked(i); }
+        }
+    });
+}
+
 #[bench]
 fn bench_63_bits_vec_set_1000_ints(b: &mut Bencher) {
     let mut vec = BitsVec::with_elements(63, 1000, 100);
@@ -152,3 +196,41 @@ fn bench_63_bits_vec_set_1000_ints(b: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn bench_22_to_40_bits_vec_widen_1000_elements(b: &mut Bencher) {
+    let vec = BitsVec::with_elements(22, 1000, 100);
+    b.iter(|| {
+        let mut vec = vec.clone();
+        vec.widen_bits(40);
+    });
+}
+
+#[bench]
+fn bench_22_to_40_bits_vec_repack_1000_elements_with_push(b: &mut Bencher) {
+    let vec = BitsVec::with_elements(22, 1000, 100);
+    b.iter(|| {
+        BitsVec::from_iter(40, vec.iter());
+    });
+}
+
+#[bench]
+fn bench_22_to_40_bits_vec_widen_1000_signed_elements(b: &mut Bencher) {
+    let mut vec = BitsVec::new_signed(22);
+    vec.extend((0..1000).map(|i| i - 500));
+    b.iter(|| {
+        let mut vec = vec.clone();
+        vec.widen_bits(40);
+    });
+}
+
+#[bench]
+fn bench_22_to_40_bits_vec_repack_1000_signed_elements_with_push(b: &mut Bencher) {
+    let mut vec = BitsVec::new_signed(22);
+    vec.extend((0..1000).map(|i| i - 500));
+    b.iter(|| {
+        let mut new = BitsVec::new_signed(40);
+        new.extend(vec.iter());
+        new
+    });
+}
@@ -1,4 +1,5 @@
 #![feature(test)]
+#![allow(deprecated)]
 
 extern crate nucleic_acid;
 #[macro_use]
@@ -13,10 +14,18 @@ use test::Bencher;
 lazy_static! {
     static ref DATA: Vec<u8> = {
         let mut rng = rand::thread_rng();
-        let bases = vec![65, 67, 71, 84];
+        let bases = [65, 67, 71, 84];
         (0..1000).map(|_| bases[rng.gen_range(0, bases.len())]).collect()
     };
 
+    static ref REPETITIVE: Vec<u8> = DATA.repeat(10);
+
+    static ref LARGE: Vec<u8> = {
+        let mut rng = rand::thread_rng();
+        let bases = [65, 67, 71, 84];
+        (0..1_000_000).map(|_| bases[rng.gen_range(0, bases.len())]).collect()
+    };
+
     static ref QUERY: String = {
         let mut rng = rand::thread_rng();
         let idx = rng.gen_range(0, DATA.len() - 100);
@@ -53,3 +62,38 @@ fn bench_fm_index_1000_random_values_get_100_chars(b: &mut Bencher) {
         index.search(&QUERY);
     })
 }
+
+#[bench]
+fn bench_suffix_array_10000_repetitive_values(b: &mut Bencher) {
+    b.iter(|| {
+        suffix_array(&REPETITIVE);
+    })
+}
+
+#[bench]
+fn bench_fm_index_10000_repetitive_values_constructor(b: &mut Bencher) {
+    b.iter(|| {
+        FMIndex::new(&REPETITIVE);
+    })
+}
+
+#[bench]
+fn bench_fm_index_10000_repetitive_values_parallel_constructor(b: &mut Bencher) {
+    b.iter(|| {
+        FMIndex::new_parallel(&REPETITIVE, 4);
+    })
+}
+
+#[bench]
+fn bench_fm_index_1000000_values_constructor(b: &mut Bencher) {
+    b.iter(|| {
+        FMIndex::new(&LARGE);
+    })
+}
+
+#[bench]
+fn bench_fm_index_1000000_values_parallel_constructor(b: &mut Bencher) {
+    b.iter(|| {
+        FMIndex::new_parallel(&LARGE, 4);
+    })
+}
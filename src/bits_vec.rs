@@ -1,9 +1,19 @@
+use bit_vec::BitVec;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(feature = "serde")]
+use serde::de::Error as DeError;
 use std::cmp;
+use std::error::Error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::iter::{StepBy, Zip};
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::Range;
-use std::usize;
 
 /// A trait for representing types as `usize` (useful for enums).
 ///
@@ -33,28 +43,39 @@ use std::usize;
 ///             _ => unimplemented!(),
 ///         }
 ///     }
+///
+///     // optional, but useful for `BitsVec::for_enum`
+///     fn bit_width() -> Option<usize> {
+///         Some(1)
+///     }
 /// }
 /// ```
 ///
 pub trait ReprUsize {
     /// Convert the value back from `usize`
-    fn from_usize(usize) -> Self;
+    fn from_usize(i: usize) -> Self;
     /// Convert the value into an `usize`
     fn into_usize(self) -> usize;
+    /// The number of bits required to represent all the values of this type (if it's known)
+    fn bit_width() -> Option<usize> { None }
 }
 
 impl ReprUsize for bool {
     fn into_usize(self) -> usize { self as usize }
-    fn from_usize(i: usize) -> bool {
-        unsafe { mem::transmute(i as u8) }
-    }
+    fn from_usize(i: usize) -> bool { i != 0 }
+    fn bit_width() -> Option<usize> { Some(1) }
 }
 
 impl ReprUsize for char {
     fn into_usize(self) -> usize { self as usize }
     fn from_usize(i: usize) -> char {
-        unsafe { mem::transmute(i as u32) }
+        match char::from_u32(i as u32) {
+            Some(c) if i <= u32::MAX as usize => c,
+            _ => panic!("[from_usize] value isn't a valid char ({})", i),
+        }
     }
+
+    fn bit_width() -> Option<usize> { Some(21) }
 }
 
 macro_rules! impl_predefined_type {
@@ -64,11 +85,18 @@ macro_rules! impl_predefined_type {
             fn from_usize(i: usize) -> $ty { i as $ty }
         }
     };
+    ($ty: ty, $bits: expr) => {
+        impl ReprUsize for $ty {
+            fn into_usize(self) -> usize { self as usize }
+            fn from_usize(i: usize) -> $ty { i as $ty }
+            fn bit_width() -> Option<usize> { Some($bits) }
+        }
+    };
 }
 
-impl_predefined_type!(u8);
-impl_predefined_type!(u16);
-impl_predefined_type!(u32);
+impl_predefined_type!(u8, 8);
+impl_predefined_type!(u16, 16);
+impl_predefined_type!(u32, 32);
 impl_predefined_type!(u64);
 impl_predefined_type!(usize);
 impl_predefined_type!(i8);
@@ -77,6 +105,116 @@ impl_predefined_type!(i32);
 impl_predefined_type!(i64);
 impl_predefined_type!(isize);
 
+// Get the minimum number of bits required to represent a value (at least one bit).
+fn min_bits(value: usize) -> usize {
+    cmp::max(1, (usize::MAX.count_ones() - value.leading_zeros()) as usize)
+}
+
+// Number of elements shown at either end of the long vectors by `Display`
+const DISPLAY_EDGE: usize = 5;
+
+// Get the number of bits after which the words holding a repeated value repeat themselves
+// (i.e., `lcm(bits, max_bits)`).
+fn period_bits(bits: usize, max_bits: usize) -> usize {
+    let (mut a, mut b) = (bits, max_bits);
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+
+    bits / a * max_bits
+}
+
+// Read the value at an index from the words packed with the given number of bits per value
+// (without any bounds checking)
+fn read_packed(inner: &[usize], bits: usize, i: usize) -> usize {
+    let max_bits = usize::MAX.count_ones() as usize;
+    let pos = i * bits;
+    let idx = pos / max_bits;
+    let offset = pos % max_bits;
+    let diff = max_bits - offset;
+    let mut val = inner[idx];
+    if offset != 0 {
+        val &= (1 << diff) - 1;
+    }
+
+    if diff >= bits {
+        val >> (diff - bits)
+    } else {
+        let shift = bits - diff;
+        let last = inner[idx + 1] >> (max_bits - shift);
+        (val << shift) | last
+    }
+}
+
+/// The error returned when a value is larger than what a `BitsVec` can hold.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PushError {
+    /// The offending value (as `usize`)
+    pub value: usize,
+    /// The number of bits allowed by the vector
+    pub bits: usize,
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "input size is more than allowed size ({} needs more than {} bits)", self.value, self.bits)
+    }
+}
+
+impl Error for PushError {}
+
+/// The error returned when a length is greater than the length of a `BitsVec`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LenError {
+    /// The offending length
+    pub length: usize,
+    /// The actual length of the vector
+    pub actual: usize,
+}
+
+impl fmt::Display for LenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "length is greater than the length of the vector ({} > {})", self.length, self.actual)
+    }
+}
+
+impl Error for LenError {}
+
+/// The error returned when a value is smaller than the last value pushed into a `DeltaBuilder`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MonotonicError {
+    /// The offending value
+    pub value: usize,
+    /// The last value pushed into the builder
+    pub last: usize,
+}
+
+impl fmt::Display for MonotonicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "input is smaller than the last value ({} < {})", self.value, self.last)
+    }
+}
+
+impl Error for MonotonicError {}
+
+/// The error returned when a stream of variable-length integers can't be decoded into
+/// a `BitsVec` (see `BitsVec::from_varint_bytes`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VarintError {
+    /// The position of the first byte of the offending value in the stream
+    pub position: usize,
+}
+
+impl fmt::Display for VarintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid (incomplete or too large) variable-length integer at position {}", self.position)
+    }
+}
+
+impl Error for VarintError {}
+
 /// A vector to hold values that have a known bit range.
 ///
 /// For example, DNA nucleotides don't need 8 bits to represent them. We know they
@@ -118,13 +256,39 @@ impl_predefined_type!(isize);
 /// The human genome has ~3 billion bases (that's 3 GB). Using 8 bits for each of them would be
 /// a waste of space. This representation reduces the memory consumed by a factor of 6.
 ///
-#[derive(Clone, Hash)]
+/// Enums and unsigned integers should use `new` (or `for_enum`, when the type knows its bit
+/// width), whereas signed integers should use `new_signed`, since the negative values (with all
+/// their high bits set) can't be held by the unsigned vectors.
+///
+/// ``` rust
+/// use nucleic_acid::BitsVec;
+///
+/// let mut vec = BitsVec::new_signed(4);
+/// vec.push(-3i8);
+/// assert_eq!(vec.get(0), -3);
+/// assert!(BitsVec::new(4).try_push(-3i8).is_err());
+/// ```
+///
+/// The vector is `Send` and `Sync` (as long as the element type is), and none of the reading
+/// methods (`get`, `iter` and friends) mutate it. So, a large vector can be shared across
+/// threads through an `Arc`, without cloning the packed data.
+///
+/// With the `serde` feature, the vector can be serialized (as its bits, length and the packed
+/// words). Note that the words are `usize`, so the serialized vectors aren't portable across
+/// architectures with different pointer widths (for that, use `to_le_words`). The deserialized
+/// fields are checked for consistency (which fails for the vectors from such architectures).
+///
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct BitsVec<T: ReprUsize> {
     inner: Vec<usize>,
     units: usize,
     bits: usize,
     max_bits: usize,
     leftover: usize,
+    signed: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
     _marker: PhantomData<T>,
 }
 
@@ -138,13 +302,53 @@ impl<T: ReprUsize> BitsVec<T> {
         BitsVec {
             inner: vec![0],
             units: 0,
-            bits: bits,
+            bits,
             max_bits: max,
             leftover: max,
+            signed: false,
             _marker: PhantomData,
         }
     }
 
+    /// Create a new vector that can hold signed values within the specified `bits`
+    /// (i.e., in the range `-2^(bits - 1)..2^(bits - 1)`).
+    ///
+    /// The values are stored in two's complement (truncated to the bits), and they're sign-extended
+    /// while getting them back. Note that they're *not* biased by `2^(bits - 1)`, so the raw bits
+    /// (from `as_words`, `to_bits`, `into_raw_parts` and friends) have the usual signed layout,
+    /// and widening the vector only extends the sign.
+    ///
+    /// This should be used for signed integers, whose `usize` representation (with all the high
+    /// bits set for negative values) could never fit in the vector otherwise. Enums and other
+    /// unsigned types should use `new` (or `for_enum`) instead.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let mut vec = BitsVec::new_signed(4);
+    /// vec.push(-8i8);
+    /// vec.push(7);
+    /// assert_eq!(vec.iter().collect::<Vec<_>>(), vec![-8, 7]);
+    /// // two's complement (a bias would've stored these as `0b0000` and `0b1111`)
+    /// assert_eq!(vec.as_big_endian_value(2), 0b1000_0111);
+    /// ```
+    pub fn new_signed(bits: usize) -> BitsVec<T> {
+        assert!(bits > 0, "[new_signed] signed values need at least one bit");
+        let mut vec = BitsVec::new(bits);
+        vec.signed = true;
+        vec
+    }
+
+    /// Create a new vector using the bit width of the element type (see `ReprUsize::bit_width`).
+    /// Note that this panics if the bit width of the type is unknown.
+    ///
+    /// This is useful for enums, since it avoids guessing the number of bits required for
+    /// representing the variants.
+    pub fn for_enum() -> BitsVec<T> {
+        let bits = T::bit_width().expect("[for_enum] bit width of the type is unknown");
+        BitsVec::new(bits)
+    }
+
     /// Creates a new vector that can hold the specified bits (atmost) and has capacity
     /// for "N" additional elements.
     pub fn with_capacity(bits: usize, capacity: usize) -> BitsVec<T> {
@@ -153,12 +357,134 @@ impl<T: ReprUsize> BitsVec<T> {
         vec
     }
 
+    /// Creates a new vector with the minimum number of bits required for holding values up to
+    /// (and including) the given value, with capacity for "N" additional elements.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::<u16>::with_max_value(1000, 0);
+    /// assert_eq!(vec.bits(), 10);
+    /// ```
+    pub fn with_max_value(max_value: usize, capacity: usize) -> BitsVec<T> {
+        BitsVec::with_capacity(min_bits(max_value), capacity)
+    }
+
+    // Get the packed representation of a value (or `None` if it can't be held by the vector)
+    fn encode(&self, value: usize) -> Option<usize> {
+        if self.signed {
+            let packed = value & ((1 << self.bits) - 1);
+            if self.extend_sign(packed) == value { Some(packed) } else { None }
+        } else if value >> self.bits == 0 {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    // Sign-extend a packed (two's complement) value to the width of `usize`
+    fn extend_sign(&self, value: usize) -> usize {
+        let shift = self.max_bits - self.bits;
+        (((value << shift) as isize) >> shift) as usize
+    }
+
+    // Get the value back from its packed representation
+    fn decode(&self, value: usize) -> T {
+        T::from_usize(if self.signed { self.extend_sign(value) } else { value })
+    }
+
+    // Panic for a value that can't be held by the vector
+    fn out_of_range(&self, op: &str, value: usize) -> ! {
+        if self.signed {
+            let half = 1isize << (self.bits - 1);
+            panic!("[{}] input is out of the allowed range ({} not in {}..{})", op, value as isize, -half, half)
+        } else {
+            panic!("[{}] input size is more than allowed size ({} >= {})", op, value, 2usize.pow(self.bits as u32))
+        }
+    }
+
     /// Push a value into the vector.
     pub fn push(&mut self, value: T) {
-        let mut value = value.into_usize();
-        assert!(value >> self.bits == 0,
-                "[push] input size is more than allowed size ({} >= {})", value, 2usize.pow(self.bits as u32));
+        let value = value.into_usize();
+        let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("push", value));
+        self.push_raw(packed);
+    }
+
+    /// Removes the last element from the vector and returns it, or `None` if the vector is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.units == 0 {
+            return None
+        }
+
+        let value = self.decode(self.read(self.units - 1));
+        self.truncate(self.units - 1);
+        Some(value)
+    }
+
+    /// Push a value into the vector, returning an error (instead of panicking) if the value
+    /// is larger than what the vector can hold.
+    pub fn try_push(&mut self, value: T) -> Result<(), PushError> {
+        let value = value.into_usize();
+        let packed = self.encode(value).ok_or(PushError { value, bits: self.bits })?;
+        self.push_raw(packed);
+        Ok(())
+    }
+
+    /// Push the elements of an iterator into the vector, stopping with an error at the first
+    /// value that's larger than what the vector can hold. Note that the elements pushed before
+    /// the offending value remain in the vector.
+    pub fn try_extend<I>(&mut self, iterable: I) -> Result<(), PushError>
+        where I: Iterator<Item=T>
+    {
+        self.reserve(iterable.size_hint().0);
+        for i in iterable {
+            self.try_push(i)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the given number of (fixed-width, little-endian) integers from a reader and push
+    /// them into the vector, without buffering the whole stream. The values are sign-extended
+    /// for signed vectors.
+    ///
+    /// This bails out at the first I/O error, or with an `InvalidData` error at the first
+    /// value that's larger than what the vector can hold. Note that the values pushed before
+    /// that remain in the vector, and that this panics if the width isn't in `1..=8` bytes.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let mut vec = BitsVec::<u16>::new(10);
+    /// vec.extend_from_reader(&[0xe8, 0x03, 0x05, 0x00][..], 2, 2).unwrap();
+    /// assert_eq!(vec.iter().collect::<Vec<_>>(), vec![1000, 5]);
+    /// ```
+    pub fn extend_from_reader<R: Read>(&mut self, mut reader: R, count: usize,
+                                       bytes_per_value: usize) -> io::Result<()> {
+        let size = mem::size_of::<usize>();
+        assert!(bytes_per_value > 0 && bytes_per_value <= size,
+                "[extend_from_reader] width should be in the range 1..={} bytes", size);
+        let mut buffer = vec![0; bytes_per_value];
+        self.reserve(count);
+        for _ in 0..count {
+            reader.read_exact(&mut buffer)?;
+            let mut value = buffer.iter().rev().fold(0, |value, &b| (value << 8) | b as usize);
+            let shift = 8 * (size - bytes_per_value);
+            if self.signed && shift > 0 {
+                value = (((value << shift) as isize) >> shift) as usize;
+            }
+
+            let packed = self.encode(value).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, PushError { value, bits: self.bits })
+            })?;
+            self.push_raw(packed);
+        }
+
+        Ok(())
+    }
 
+    // Push a packed value into the vector (without any size checking)
+    fn push_raw(&mut self, mut value: usize) {
         let mut idx = self.inner.len() - 1;
         if self.leftover < self.bits {
             let left = self.bits - self.leftover;
@@ -183,23 +509,25 @@ impl<T: ReprUsize> BitsVec<T> {
     /// and so it panics when the index is out of bounds. For the non-panicking version, use `checked_get`
     pub fn get(&self, i: usize) -> T {
         assert!(i < self.units, "[get] index out of bounds ({} >= {})", i, self.units);
+        self.decode(self.read(i))
+    }
 
-        let pos = i * self.bits;
-        let idx = pos / self.max_bits;
-        let bits = pos % self.max_bits;
-        let diff = self.max_bits - bits;
-        let mut val = self.inner[idx];
-        if bits != 0 {
-            val &= (1 << diff) - 1;
-        }
+    // Read the packed value at an index (without any bounds checking)
+    fn read(&self, i: usize) -> usize {
+        read_packed(&self.inner, self.bits, i)
+    }
 
-        if diff >= self.bits {
-            T::from_usize(val >> (diff - self.bits))
-        } else {
-            let shift = self.bits - diff;
-            let last = self.inner[idx + 1] >> (self.max_bits - shift);
-            T::from_usize((val << shift) | last)
-        }
+    /// Get the value from an index in the vector, without checking whether the index is within
+    /// bounds (the value is decoded exactly like `get`). This is useful for the hot loops that
+    /// have already checked the bounds.
+    ///
+    /// # Safety
+    ///
+    /// The index should be less than the length of the vector. Calling this with an out-of-bounds
+    /// index is undefined behavior (even if the inner vector happens to have the bits).
+    #[inline]
+    pub unsafe fn get_unchecked(&self, i: usize) -> T {
+        self.decode(self.read(i))
     }
 
     /// Returns `Some(T)` if the element exists at the given index or `None` if it doesn't.
@@ -216,9 +544,243 @@ impl<T: ReprUsize> BitsVec<T> {
     pub fn set(&mut self, i: usize, value: T) {
         let value = value.into_usize();
         assert!(i < self.units, "[set] index out of bounds ({} >= {})", i, self.units);
-        assert!(value >> self.bits == 0,
-                "[set] input size is more than allowed size ({} >= {})", value, 2usize.pow(self.bits as u32));
+        let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("set", value));
+        self.write(i, packed);
+    }
+
+    /// Reorder the elements (in place, without decoding them), so that the new element at `i`
+    /// is the old element at `perm[i]`. Note that this panics if the given indices aren't
+    /// a permutation of `0..len`.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let mut vec = BitsVec::from_iter(5, vec![10u8, 20, 30].into_iter());
+    /// vec.apply_permutation(&[2, 0, 1]);
+    /// assert_eq!(vec.iter().collect::<Vec<_>>(), vec![30, 10, 20]);
+    /// ```
+    pub fn apply_permutation(&mut self, perm: &[usize]) {
+        assert!(perm.len() == self.units,
+                "[apply_permutation] expected {} indices (found {})", self.units, perm.len());
+        let mut seen = vec![false; self.units];
+        for &i in perm {
+            assert!(i < self.units && !seen[i], "[apply_permutation] indices should be a permutation");
+            seen[i] = true;
+        }
+
+        // Follow the cycles of the permutation, holding the first (raw) value of every cycle (the
+        // visited positions are cleared in `seen`).
+        for i in 0..self.units {
+            if !seen[i] {
+                continue
+            }
+
+            let first = self.read(i);
+            let mut j = i;
+            while perm[j] != i {
+                let value = self.read(perm[j]);
+                self.write(j, value);
+                seen[j] = false;
+                j = perm[j];
+            }
+
+            self.write(j, first);
+            seen[j] = false;
+        }
+    }
+
+    /// Replace every element with the result of calling the function with it (in place).
+    /// Note that this panics (like `set`) when a result can't be held by the vector.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let mut counters = BitsVec::from_iter(4, vec![0u8, 3, 14].into_iter());
+    /// counters.for_each_mut(|c| c + 1);
+    /// assert_eq!(counters.iter().collect::<Vec<_>>(), vec![1, 4, 15]);
+    /// ```
+    pub fn for_each_mut<F>(&mut self, mut f: F)
+        where F: FnMut(T) -> T
+    {
+        if self.units == 0 {
+            return
+        }
+
+        // Walk through the words, carrying the current word (which is written once it's done)
+        // and the number of its bits that have been visited.
+        let (bits, max_bits) = (self.bits, self.max_bits);
+        let (mut idx, mut used) = (0, 0);
+        let mut word = self.inner[0];
+        for _ in 0..self.units {
+            if used == max_bits {
+                self.inner[idx] = word;
+                idx += 1;
+                word = self.inner[idx];
+                used = 0;
+            }
 
+            let free = max_bits - used;
+            if bits <= free {
+                let shift = free - bits;
+                let mask = ((1 << bits) - 1) << shift;
+                let value = f(self.decode((word & mask) >> shift)).into_usize();
+                let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("for_each_mut", value));
+                word = (word & !mask) | (packed << shift);
+                used += bits;
+            } else {    // the element continues in the next word
+                let (rest, next) = (bits - free, self.inner[idx + 1]);
+                let low = max_bits - rest;
+                let high = word & ((1 << free) - 1);
+                let value = f(self.decode((high << rest) | (next >> low))).into_usize();
+                let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("for_each_mut", value));
+                self.inner[idx] = (word & !((1 << free) - 1)) | (packed >> rest);
+                idx += 1;
+                word = (next & ((1 << low) - 1)) | (packed << low);
+                used = rest;
+            }
+        }
+
+        self.inner[idx] = word;
+    }
+
+    /// Swaps the elements at the given indices (without decoding them). Note that this panics
+    /// when either of the indices is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.units && j < self.units,
+                "[swap] index out of bounds ({}, {} for length {})", i, j, self.units);
+        if i != j {
+            let (a, b) = (self.read(i), self.read(j));
+            self.write(i, b);
+            self.write(j, a);
+        }
+    }
+
+    /// Reverses the order of the elements in place, by swapping them from both the ends
+    /// towards the middle (without decoding them).
+    pub fn reverse(&mut self) {
+        for i in 0..(self.units / 2) {
+            let j = self.units - 1 - i;
+            self.swap(i, j);
+        }
+    }
+
+    /// Set all the elements in the given range to the value. Note that this panics when the range
+    /// is out of bounds, or when the value can't be held by the vector.
+    ///
+    /// Once the range reaches a word boundary, the words holding the repeating pattern of the
+    /// value (one period) are written, and then copied for the rest of the range.
+    pub fn fill_range(&mut self, range: Range<usize>, value: T) {
+        assert!(range.start <= range.end && range.end <= self.units,
+                "[fill_range] range out of bounds ({}..{} for length {})", range.start, range.end, self.units);
+        let value = value.into_usize();
+        let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("fill_range", value));
+        let (bits, max_bits) = (self.bits, self.max_bits);
+        if bits == 0 {      // nothing to write (and no period of words)
+            return
+        }
+
+        let mut i = range.start;
+        while i < range.end && !(i * bits).is_multiple_of(max_bits) {
+            self.write(i, packed);
+            i += 1;
+        }
+
+        let period = period_bits(bits, max_bits);
+        let (period_units, period_words) = (period / bits, period / max_bits);
+        if range.end - i >= 2 * period_units {
+            for j in i..(i + period_units) {
+                self.write(j, packed);
+            }
+
+            let first = i * bits / max_bits;
+            i += period_units;
+            while range.end - i >= period_units {
+                let word = i * bits / max_bits;
+                self.inner.copy_within(first..first + period_words, word);
+                i += period_units;
+            }
+        }
+
+        for j in i..range.end {
+            self.write(j, packed);
+        }
+    }
+
+    /// Shift the elements to the left (towards the start) by "N" positions, dropping the first
+    /// "N" elements and filling the vacated positions at the end with the value. Unlike a rotation,
+    /// this isn't circular. Note that this panics when the value can't be held by the vector.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let mut vec = BitsVec::from_slice(4, &[1u8, 2, 3, 4, 5]);
+    /// vec.shift_left(2, 15);
+    /// assert_eq!(vec.to_vec(), vec![3, 4, 5, 15, 15]);
+    /// ```
+    pub fn shift_left(&mut self, n: usize, fill: T) {
+        let n = cmp::min(n, self.units);
+        let value = fill.into_usize();
+        let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("shift_left", value));
+        for i in n..self.units {
+            let value = self.read(i);
+            self.write(i - n, value);
+        }
+
+        for i in (self.units - n)..self.units {
+            self.write(i, packed);
+        }
+    }
+
+    /// Shift the elements to the right (towards the end) by "N" positions, dropping the last
+    /// "N" elements and filling the vacated positions at the start with the value (see `shift_left`).
+    pub fn shift_right(&mut self, n: usize, fill: T) {
+        let n = cmp::min(n, self.units);
+        let value = fill.into_usize();
+        let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("shift_right", value));
+        for i in (n..self.units).rev() {
+            let value = self.read(i - n);
+            self.write(i, value);
+        }
+
+        for i in 0..n {
+            self.write(i, packed);
+        }
+    }
+
+    /// Add to the value at the given index, clamping the result to the largest value that the
+    /// vector can hold (instead of panicking like `set`). This is useful for packed counters.
+    /// Note that this panics when the index is out of bounds.
+    pub fn saturating_add_at(&mut self, i: usize, delta: usize) {
+        assert!(i < self.units, "[saturating_add_at] index out of bounds ({} >= {})", i, self.units);
+        let mask = (1 << self.bits) - 1;
+        let value = if self.signed {
+            let max = (1isize << (self.bits - 1)) - 1;
+            let value = (self.extend_sign(self.read(i)) as isize).saturating_add(cmp::min(delta, isize::MAX as usize) as isize);
+            cmp::min(value, max) as usize & mask
+        } else {
+            cmp::min(self.read(i).saturating_add(delta), mask)
+        };
+
+        self.write(i, value);
+    }
+
+    /// Subtract from the value at the given index, clamping the result to the smallest value that
+    /// the vector can hold (zero, unless it's signed). Note that this panics when the index is out of bounds.
+    pub fn saturating_sub_at(&mut self, i: usize, delta: usize) {
+        assert!(i < self.units, "[saturating_sub_at] index out of bounds ({} >= {})", i, self.units);
+        let value = if self.signed {
+            let min = -(1isize << (self.bits - 1));
+            let value = (self.extend_sign(self.read(i)) as isize).saturating_sub(cmp::min(delta, isize::MAX as usize) as isize);
+            cmp::max(value, min) as usize & ((1 << self.bits) - 1)
+        } else {
+            self.read(i).saturating_sub(delta)
+        };
+
+        self.write(i, value);
+    }
+
+    // Write a packed value at an index (without any bounds or size checking)
+    fn write(&mut self, i: usize, value: usize) {
         let pos = i * self.bits;
         let idx = pos / self.max_bits;
         let bits = pos % self.max_bits;
@@ -243,11 +805,12 @@ impl<T: ReprUsize> BitsVec<T> {
         }
     }
 
-    /// Creates a vector consuming an iterator of elements.
+    /// Creates a vector consuming an iterator of elements. The vector reserves space
+    /// for the (lower bound of the) iterator's size hint beforehand.
     pub fn from_iter<I>(bits: usize, iterable: I) -> BitsVec<T>
         where I: Iterator<Item=T>
     {
-        let mut vec = BitsVec::new(bits);
+        let mut vec = BitsVec::with_capacity(bits, iterable.size_hint().0);
         for i in iterable {
             vec.push(i);
         }
@@ -255,6 +818,276 @@ impl<T: ReprUsize> BitsVec<T> {
         vec
     }
 
+    /// Creates a vector consuming an iterator of elements, with the minimum number of bits
+    /// required for holding its largest element (an empty vector has one bit).
+    ///
+    /// The elements are buffered (as `usize`) before packing, since the number of bits is known
+    /// only after seeing all of them. So, this temporarily needs a word for each element, on
+    /// top of the vector itself. Use `with_max_value` if the largest value is known beforehand.
+    pub fn from_iter_auto<I>(iterable: I) -> BitsVec<T>
+        where I: Iterator<Item=T>
+    {
+        let values = iterable.map(|i| i.into_usize()).collect::<Vec<_>>();
+        let max = values.iter().cloned().max().unwrap_or(0);
+        let mut vec = BitsVec::with_capacity(min_bits(max), values.len());
+        for value in values {
+            vec.push_raw(value);
+        }
+
+        vec
+    }
+
+    /// Creates a vector consuming an iterator of elements, returning an error (instead of
+    /// panicking) at the first value that's larger than what the vector can hold.
+    pub fn try_from_iter<I>(bits: usize, iterable: I) -> Result<BitsVec<T>, PushError>
+        where I: Iterator<Item=T>
+    {
+        let mut vec = BitsVec::with_capacity(bits, iterable.size_hint().0);
+        for i in iterable {
+            vec.try_push(i)?;
+        }
+
+        Ok(vec)
+    }
+
+    // Get the packed bits of the elements as bytes (with the first element in the most
+    // significant bits of the first byte), ignoring the unused bytes at the end.
+    fn packed_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.inner.iter().flat_map(|w| w.to_be_bytes().to_vec()).collect::<Vec<_>>();
+        bytes.truncate((self.units * self.bits).div_ceil(8));
+        bytes
+    }
+
+    // Create a vector of the given length from the packed bytes (see `packed_bytes`).
+    fn from_packed_bytes(bits: usize, units: usize, bytes: &[u8]) -> BitsVec<T> {
+        let mut vec = BitsVec::new(bits);
+        let size = mem::size_of::<usize>();
+        let total = units * bits;
+        assert!(bytes.len() * 8 >= total, "[from_packed] expected {} bits (found {})", total, bytes.len() * 8);
+        vec.inner = bytes[..total.div_ceil(8)].chunks(size).map(|c| {
+            let word = c.iter().fold(0, |word, &b| (word << 8) | b as usize);
+            word << (8 * (size - c.len()))
+        }).collect();
+
+        let used = total % vec.max_bits;
+        if vec.inner.is_empty() {
+            vec.inner.push(0);
+        } else if used > 0 {
+            let last = vec.inner.len() - 1;
+            vec.inner[last] &= !((1 << (vec.max_bits - used)) - 1);
+        }
+
+        vec.units = units;
+        vec.leftover = match used {
+            0 if units > 0 => 0,
+            used => vec.max_bits - used,
+        };
+        vec
+    }
+
+    /// Get the packed bits of the vector as 64-bit little-endian words (regardless of the
+    /// architecture), which is a portable format for writing the vector to files.
+    ///
+    /// The elements are packed from the most significant bits of the first word (as they are
+    /// in the vector), and the unused bits at the end are zero. The words can be written in
+    /// the native byte order, and `from_le_words` gets the vector back on any host.
+    pub fn to_le_words(&self) -> Vec<u64> {
+        self.packed_bytes().chunks(8).map(|c| {
+            let word = c.iter().fold(0, |word, &b| (word << 8) | b as u64);
+            (word << (8 * (8 - c.len()))).to_le()
+        }).collect()
+    }
+
+    /// Create a vector of the given length (and bits) from the words generated by `to_le_words`.
+    /// Note that this panics if the words don't have enough bits for the elements.
+    pub fn from_le_words(bits: usize, units: usize, words: &[u64]) -> BitsVec<T> {
+        let bytes = words.iter().flat_map(|w| u64::from_le(*w).to_be_bytes().to_vec()).collect::<Vec<_>>();
+        BitsVec::from_packed_bytes(bits, units, &bytes)
+    }
+
+    /// Encode the elements as a stream of variable-length integers
+    /// ([LEB128](https://en.wikipedia.org/wiki/LEB128)), which is compact when most values are small.
+    ///
+    /// The values of signed vectors are [zigzag-encoded](https://en.wikipedia.org/wiki/Variable-length_quantity#Zigzag_encoding)
+    /// (so that the small negative values are small as well), and they should be decoded
+    /// with `from_varint_bytes_signed`.
+    pub fn to_varint_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.units);
+        for i in 0..self.units {
+            let mut value = self.read(i);
+            if self.signed {
+                let value_signed = self.extend_sign(value) as isize;
+                value = ((value_signed << 1) ^ (value_signed >> (self.max_bits - 1))) as usize;
+            }
+
+            while value >= 0x80 {
+                bytes.push((value & 0x7f) as u8 | 0x80);
+                value >>= 7;
+            }
+
+            bytes.push(value as u8);
+        }
+
+        bytes
+    }
+
+    // Decode the values from a stream of variable-length integers (see `from_varint_bytes`)
+    fn read_varints(bytes: &[u8]) -> Result<Vec<usize>, VarintError> {
+        let max_bits = usize::MAX.count_ones() as usize;
+        let mut values = Vec::new();
+        let (mut value, mut shift, mut start) = (0, 0, 0);
+        for (i, &b) in bytes.iter().enumerate() {
+            let chunk = (b & 0x7f) as usize;
+            if shift >= max_bits || (chunk << shift) >> shift != chunk {
+                return Err(VarintError { position: start })
+            }
+
+            value |= chunk << shift;
+            shift += 7;
+            if b & 0x80 == 0 {
+                if value >> (max_bits - 1) != 0 {
+                    return Err(VarintError { position: start })
+                }
+
+                values.push(value);
+                value = 0;
+                shift = 0;
+                start = i + 1;
+            }
+        }
+
+        if shift != 0 {
+            return Err(VarintError { position: start })
+        }
+
+        Ok(values)
+    }
+
+    /// Decode a vector from a stream of variable-length integers (see `to_varint_bytes`). The
+    /// number of bits is chosen from the largest value in the stream. This returns an error
+    /// when the stream ends in the middle of a value, or when a value doesn't fit in the vector
+    /// (which can hold up to one bit less than `usize`).
+    pub fn from_varint_bytes(bytes: &[u8]) -> Result<BitsVec<T>, VarintError> {
+        let values = BitsVec::<T>::read_varints(bytes)?;
+        let max = values.iter().cloned().max().unwrap_or(0);
+        let mut vec = BitsVec::with_capacity(min_bits(max), values.len());
+        for value in values {
+            vec.push_raw(value);
+        }
+
+        Ok(vec)
+    }
+
+    /// Decode a signed vector from the zigzag-encoded variable-length integers generated by
+    /// `to_varint_bytes` (for signed vectors). The number of bits is chosen from the value with
+    /// the largest magnitude, and the errors are the same as those of `from_varint_bytes`.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let mut vec = BitsVec::new_signed(12);
+    /// vec.extend(vec![-1i16, 1, -2000]);
+    /// let bytes = vec.to_varint_bytes();
+    /// assert_eq!(&bytes[..2], &[1, 2]);
+    /// let decoded = BitsVec::<i16>::from_varint_bytes_signed(&bytes).unwrap();
+    /// assert_eq!(decoded.iter().collect::<Vec<_>>(), vec![-1, 1, -2000]);
+    /// ```
+    pub fn from_varint_bytes_signed(bytes: &[u8]) -> Result<BitsVec<T>, VarintError> {
+        let values = BitsVec::<T>::read_varints(bytes)?;
+        let max = values.iter().cloned().max().unwrap_or(0);
+        let mut vec = BitsVec::new_signed(min_bits(max));
+        vec.reserve(values.len());
+        for value in values {
+            let value = ((value >> 1) as isize ^ -((value & 1) as isize)) as usize;
+            vec.push_raw(value & ((1 << vec.bits) - 1));
+        }
+
+        Ok(vec)
+    }
+
+    /// Expand the elements into their individual bits (the most significant bit of each element
+    /// first), which gives `len * bits` booleans. Note that these are the packed values (i.e.,
+    /// two's complement without the sign extension, for the signed vectors).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(3, &[6u8, 1]);
+    /// assert_eq!(vec.to_bits(), vec![true, true, false, false, false, true]);
+    /// assert_eq!(BitsVec::from_bits(3, &vec.to_bits()), vec);
+    /// ```
+    pub fn to_bits(&self) -> Vec<bool> {
+        let mut bits = Vec::with_capacity(self.units * self.bits);
+        for i in 0..self.units {
+            let value = self.read(i);
+            bits.extend((0..self.bits).rev().map(|b| (value >> b) & 1 == 1));
+        }
+
+        bits
+    }
+
+    /// Creates a vector (with the specified bits) from the expanded bits of its elements
+    /// (see `to_bits`). Note that this panics if the number of booleans isn't a multiple of the bits.
+    pub fn from_bits(bits: usize, values: &[bool]) -> BitsVec<T> {
+        let mut vec = BitsVec::new(bits);
+        assert!(values.len().is_multiple_of(bits),
+                "[from_bits] number of bits should be a multiple of {} (got {})", bits, values.len());
+        vec.reserve(values.len() / bits);
+        for chunk in values.chunks(bits) {
+            vec.push_raw(chunk.iter().fold(0, |value, &b| (value << 1) | b as usize));
+        }
+
+        vec
+    }
+
+    /// Run-length encode the vector, returning the values of the runs (with the same bits as
+    /// this vector) and their lengths (with the bits chosen from the longest run). This is
+    /// a lot more compact for the vectors with long runs of the same value (like BWT outputs).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(2, &[3u8, 3, 3, 0, 0, 1, 3, 3]);
+    /// let (values, lengths) = vec.to_rle();
+    /// assert_eq!(values.to_vec(), vec![3, 0, 1, 3]);
+    /// assert_eq!(lengths.to_vec(), vec![3, 2, 1, 2]);
+    /// assert_eq!(BitsVec::from_rle(&values, &lengths), vec);
+    /// ```
+    pub fn to_rle(&self) -> (BitsVec<T>, BitsVec<u64>) {
+        let mut values = BitsVec::new(self.bits);
+        values.signed = self.signed;
+        let mut lengths = Vec::new();
+        for i in 0..self.units {
+            let value = self.read(i);
+            if i > 0 && value == self.read(i - 1) {
+                *lengths.last_mut().unwrap() += 1;
+            } else {
+                values.push_raw(value);
+                lengths.push(1);
+            }
+        }
+
+        let max = lengths.iter().cloned().max().unwrap_or(0);
+        (values, BitsVec::from_slice(min_bits(max as usize), &lengths))
+    }
+
+    /// Decode a vector from the values of its runs and their lengths (see `to_rle`). Note that
+    /// this panics when the number of values and lengths differ.
+    pub fn from_rle(values: &BitsVec<T>, lengths: &BitsVec<u64>) -> BitsVec<T> {
+        assert_eq!(values.units, lengths.units, "[from_rle] values and lengths should have the same length");
+        let total = lengths.iter().sum::<u64>() as usize;
+        let mut vec = BitsVec::with_capacity(values.bits, total);
+        vec.signed = values.signed;
+        for i in 0..values.units {
+            let value = values.read(i);
+            for _ in 0..lengths.get(i) {
+                vec.push_raw(value);
+            }
+        }
+
+        vec
+    }
+
     /// Returns the length of the vector. This only indicates the number of units it contains,
     /// and not the length of the inner vector.
     #[inline]
@@ -282,9 +1115,23 @@ impl<T: ReprUsize> BitsVec<T> {
     }
 
     /// Truncate the vector to the given length, removing the out-of-bound elements. Note that this
-    /// method panics when the length is greater than current length.
+    /// method panics when the length is greater than current length. For the non-panicking version,
+    /// use `checked_truncate`.
     pub fn truncate(&mut self, length: usize) {
-        assert!(length < self.units, "length should be smaller for truncation ({} >= {})", length, self.units);
+        if let Err(e) = self.checked_truncate(length) {
+            panic!("[truncate] {}", e);
+        }
+    }
+
+    /// Truncate the vector to the given length, or return an error if the length is greater
+    /// than the current length (truncating to the current length does nothing).
+    pub fn checked_truncate(&mut self, length: usize) -> Result<(), LenError> {
+        if length > self.units {
+            return Err(LenError { length, actual: self.units })
+        } else if length == self.units {
+            return Ok(())
+        }
+
         self.units = length;
 
         let bits = length * self.bits;
@@ -302,32 +1149,497 @@ impl<T: ReprUsize> BitsVec<T> {
             self.inner.push(0);
             self.leftover = self.max_bits;
         }
-    }
 
-    /// Clears the inner vector. Note that this is similar to calling `truncate` with zero.
-    #[inline]
-    pub fn clear(&mut self) {
-        self.truncate(0);
+        Ok(())
     }
 
-    /// Returns the length of the inner vector. Useful for measuring the memory consumption
-    /// of the elements.
+    /// Removes the first "N" elements from the vector and returns them, shifting the remaining
+    /// elements to the front. This is the front counterpart of `truncate`, and it panics when
+    /// "N" is greater than the current length.
+    pub fn drain_front(&mut self, n: usize) -> Vec<T> {
+        assert!(n <= self.units, "[drain] cannot drain more than the length ({} > {})", n, self.units);
+        let drained = (0..n).map(|i| self.get(i)).collect();
+        if n == 0 {
+            return drained
+        }
+
+        for i in n..self.units {
+            let value = self.read(i);
+            self.write(i - n, value);
+        }
+
+        let length = self.units - n;
+        self.truncate(length);
+        drained
+    }
+
+    /// Inserts an element at the given index, shifting the following elements to the right.
+    /// This takes O(n) time, and panics when the index is greater than the length (inserting
+    /// at the length is the same as `push`), or when the value can't be held by the vector.
+    pub fn insert(&mut self, i: usize, value: T) {
+        assert!(i <= self.units, "[insert] index out of bounds ({} > {})", i, self.units);
+        let value = value.into_usize();
+        let packed = self.encode(value).unwrap_or_else(|| self.out_of_range("insert", value));
+        if i == self.units {
+            return self.push_raw(packed)
+        }
+
+        let last = self.read(self.units - 1);
+        self.push_raw(last);
+        for j in (i + 1..self.units - 1).rev() {
+            let value = self.read(j - 1);
+            self.write(j, value);
+        }
+
+        self.write(i, packed);
+    }
+
+    /// Moves all the elements of the other vector into this vector, leaving the other vector
+    /// empty. Note that this panics when the vectors have different bits.
+    ///
+    /// If this vector ends at a word boundary, then the words of the other vector are copied
+    /// directly. Otherwise, the elements are repacked across the boundary.
+    pub fn append(&mut self, other: &mut BitsVec<T>) {
+        assert!(self.bits == other.bits && self.signed == other.signed,
+                "[append] vectors should have the same bits ({} != {})", self.bits, other.bits);
+        if self.leftover == self.max_bits || self.leftover == 0 {
+            if self.leftover == self.max_bits {
+                self.inner.pop();       // unused word
+            }
+
+            self.inner.extend_from_slice(&other.inner);
+            self.leftover = other.leftover;
+            self.units += other.units;
+        } else {
+            self.reserve(other.units);
+            for i in 0..other.units {
+                self.push_raw(other.read(i));
+            }
+        }
+
+        other.clear();
+    }
+
+    /// Removes the element at the given index and returns it, shifting the following elements
+    /// to the left. This takes O(n) time, and panics when the index is out of bounds.
+    pub fn remove(&mut self, i: usize) -> T {
+        assert!(i < self.units, "[remove] index out of bounds ({} >= {})", i, self.units);
+        let removed = self.decode(self.read(i));
+        for j in (i + 1)..self.units {
+            let value = self.read(j);
+            self.write(j - 1, value);
+        }
+
+        let length = self.units - 1;
+        self.truncate(length);
+        removed
+    }
+
+    /// Resize the vector to the given length. If the vector grows, then the additional elements
+    /// are generated by calling the function with their indices (and this panics if any of them
+    /// can't be held by the vector). Otherwise, the vector is truncated.
+    pub fn resize_with<F>(&mut self, new_len: usize, mut f: F)
+        where F: FnMut(usize) -> T
+    {
+        if new_len < self.units {
+            self.truncate(new_len);
+            return
+        }
+
+        self.reserve(new_len - self.units);
+        for i in self.units..new_len {
+            self.push(f(i));
+        }
+    }
+
+    /// Clears the inner vector. Note that this is similar to calling `truncate` with zero.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Widen the vector to hold values of the specified `bits`, repacking all the elements.
+    /// Since the new width is at least the current width, this never loses any data. Note
+    /// that this panics when the new width is smaller than the current width.
+    pub fn widen_bits(&mut self, new_bits: usize) {
+        assert!(new_bits >= self.bits, "[widen] new bits should be at least the current bits ({} < {})",
+                new_bits, self.bits);
+        if new_bits == self.bits {
+            return
+        }
+
+        // Walk through the packed words (instead of indexing every element), carrying the
+        // unread bits of the current word and the unflushed bits of the new word.
+        let (bits, max_bits) = (self.bits, self.max_bits);
+        let mut inner = Vec::with_capacity((self.units * new_bits) / max_bits + 1);
+        let (mut src, mut unread) = (0, max_bits);
+        let (mut word, mut used) = (0, 0);
+        for _ in 0..self.units {
+            let mut value = if unread >= bits {
+                unread -= bits;
+                (self.inner[src] >> unread) & ((1 << bits) - 1)
+            } else {
+                let rest = bits - unread;
+                let high = self.inner[src] & ((1 << unread) - 1);
+                src += 1;
+                unread = max_bits - rest;
+                (high << rest) | (self.inner[src] >> unread)
+            };
+
+            if self.signed {    // the sign extends into the new bits
+                value = self.extend_sign(value) & ((1 << new_bits) - 1);
+            }
+
+            let free = max_bits - used;
+            if new_bits < free {
+                word |= value << (free - new_bits);
+                used += new_bits;
+            } else {
+                let spill = new_bits - free;
+                inner.push(word | (value >> spill));
+                word = if spill == 0 { 0 } else { value << (max_bits - spill) };
+                used = spill;
+            }
+        }
+
+        if used > 0 || inner.is_empty() {
+            inner.push(word);
+            self.leftover = max_bits - used;
+        } else {
+            self.leftover = 0;
+        }
+
+        self.inner = inner;
+        self.bits = new_bits;
+    }
+
+    /// Returns the number of bits used by each element in the vector.
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Checks whether the vector holds signed values (i.e., whether it's been created by `new_signed`).
+    #[inline]
+    pub fn is_signed(&self) -> bool {
+        self.signed
+    }
+
+    /// Get a (read-only) view of the vector, which decodes the packed bits as elements of a
+    /// different width, without copying them. For example, a 16-bit vector can be viewed as
+    /// an 8-bit vector (with twice the elements), where each pair has the high and low bytes
+    /// of an element. Note that this panics if the total number of bits in the vector is not
+    /// a multiple of the given bits.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_iter(16, vec![0x1234u16, 0xabcd].into_iter());
+    /// let view = vec.view_as(8);
+    /// assert_eq!(view.iter().collect::<Vec<_>>(), vec![0x12, 0x34, 0xab, 0xcd]);
+    /// ```
+    ///
+    /// The elements are the raw bits, and so the values in signed vectors aren't sign-extended.
+    pub fn view_as(&self, bits: usize) -> BitsVecView<'_> {
+        assert!(bits > 0 && bits < self.max_bits,
+                "[view_as] bits should be in the range 1..{}", self.max_bits);
+        let total = self.units * self.bits;
+        assert!(total.is_multiple_of(bits), "[view_as] {} bits cannot be viewed as {}-bit elements", total, bits);
+        BitsVecView {
+            inner: &self.inner,
+            units: total / bits,
+            bits,
+        }
+    }
+
+    /// Returns the length of the inner vector. Useful for measuring the memory consumption
+    /// of the elements.
     #[inline]
     pub fn inner_len(&self) -> usize {
         self.inner.len()
     }
 
+    /// Returns the number of bytes consumed by the vector - its inner vector (on the heap)
+    /// and the vector itself.
+    ///
+    /// This accounts for the capacity of the inner vector (i.e., the memory that's actually
+    /// been allocated), which can be larger than what's used by the elements (`inner_len`
+    /// words), especially after reserving (or removing) a lot of elements. Use `shrink_to_fit`
+    /// to release the unused capacity.
+    pub fn memory_bytes(&self) -> usize {
+        self.inner.capacity() * mem::size_of::<usize>() + mem::size_of::<BitsVec<T>>()
+    }
+
+    /// Returns the number of bits used by each element in the vector (same as `bits`).
+    #[inline]
+    pub fn bits_per_element(&self) -> usize {
+        self.bits
+    }
+
+    // Get the inner words holding the elements, with the unused bits (of the last word) cleared.
+    fn significant_words(&self) -> impl Iterator<Item=usize> + '_ {
+        let bits = self.units * self.bits;
+        let (words, used) = (bits.div_ceil(self.max_bits), bits % self.max_bits);
+        let mask = if used > 0 { !((1 << (self.max_bits - used)) - 1) } else { usize::MAX };
+        self.inner[..words].iter().enumerate().map(move |(i, &word)| {
+            if i + 1 == words { word & mask } else { word }
+        })
+    }
+
+    /// Get the reference to the inner (packed) words of the vector.
+    ///
+    /// The unused bits (and words) at the end may have some leftovers from the previous
+    /// operations, and so the words of equal vectors could differ. Use `canonicalize` before
+    /// hashing (or serializing) the words.
+    #[inline]
+    pub fn as_words(&self) -> &[usize] {
+        &self.inner
+    }
+
+    /// Get the reference to the inner (packed) words of the vector, for writing them out
+    /// as such (same as `as_words`).
+    ///
+    /// The words are in the native endianness and pointer width, so the bytes written on one
+    /// machine can only be read back (using `from_raw_parts`) on a machine of the same kind.
+    /// For a portable representation, use `to_le_words`.
+    #[inline]
+    pub fn as_raw_slice(&self) -> &[usize] {
+        &self.inner
+    }
+
+    /// Returns the number of words in the inner vector (same as `inner_len`).
+    #[inline]
+    pub fn word_count(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Clears the unused bits in the last word, and removes any unused words at the end, so
+    /// that the inner words of equal vectors are identical.
+    pub fn canonicalize(&mut self) {
+        let bits = self.units * self.bits;
+        let used = bits % self.max_bits;
+        let words = bits.div_ceil(self.max_bits);
+        self.inner.truncate(cmp::max(words, 1));
+        if used > 0 {
+            self.inner[words - 1] &= !((1 << (self.max_bits - used)) - 1);
+            self.leftover = self.max_bits - used;
+        } else if words > 0 {
+            self.leftover = 0;
+        } else {
+            self.inner[0] = 0;
+            self.leftover = self.max_bits;
+        }
+    }
+
+    /// Creates a vector from its inner (packed) words, with the specified bits and length, without
+    /// copying the words. The unused bits (and words) at the end are cleared (see `canonicalize`).
+    /// For the vectors of signed values, use `from_raw_parts_signed`.
+    ///
+    /// # Safety
+    ///
+    /// This doesn't validate the words, and they're trusted to be packed like the words from
+    /// `as_words` or `into_raw_parts` (on a machine with the same pointer width). Otherwise, the
+    /// elements decoded from them are meaningless (and may not even be values of `T`, in which
+    /// case `T::from_usize` may panic). Note that this panics if the words can't hold `units * bits`
+    /// bits, or (like `new`) if the vector can't have the given bits.
+    pub unsafe fn from_raw_parts(inner: Vec<usize>, bits: usize, units: usize) -> BitsVec<T> {
+        BitsVec::from_raw_parts_with(BitsVec::new(bits), inner, units)
+    }
+
+    /// Creates a vector of signed values (see `new_signed`) from its inner (packed) words, with the
+    /// specified bits and length. This is similar to `from_raw_parts`, except that the words should
+    /// have the values in two's complement (like the words of a vector created by `new_signed`).
+    ///
+    /// # Safety
+    ///
+    /// The words are trusted in the same way as `from_raw_parts`.
+    pub unsafe fn from_raw_parts_signed(inner: Vec<usize>, bits: usize, units: usize) -> BitsVec<T> {
+        BitsVec::from_raw_parts_with(BitsVec::new_signed(bits), inner, units)
+    }
+
+    // Put the given words into an empty vector.
+    fn from_raw_parts_with(mut vec: BitsVec<T>, inner: Vec<usize>, units: usize) -> BitsVec<T> {
+        assert!(inner.len() * vec.max_bits >= units * vec.bits,
+                "[from_raw_parts] words can't hold the elements ({} words for {} bits)", inner.len(), units * vec.bits);
+        if !inner.is_empty() {
+            vec.inner = inner;
+        }
+
+        vec.units = units;
+        vec.canonicalize();
+        vec
+    }
+
+    /// Decomposes the vector into its inner (packed) words, bits and length (see `from_raw_parts`).
+    /// Note that the signedness isn't a part of this (see `is_signed`).
+    pub fn into_raw_parts(self) -> (Vec<usize>, usize, usize) {
+        (self.inner, self.bits, self.units)
+    }
+
+    /// Render a vector of 2-bit nucleotides as a DNA string (useful for debugging). The values
+    /// `0`, `1`, `2` and `3` are rendered as `A`, `C`, `G` and `T` respectively. Note that this
+    /// panics when the vector doesn't hold 2-bit values.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_iter(2, vec![0u8, 1, 2, 3, 3, 0].into_iter());
+    /// assert_eq!(vec.fmt_dna(), "ACGTTA");
+    /// ```
+    pub fn fmt_dna(&self) -> String {
+        assert!(self.bits == 2, "[fmt_dna] expected 2-bit values (found {} bits)", self.bits);
+        (0..self.units).map(|i| match self.read(i) {
+            0 => 'A',
+            1 => 'C',
+            2 => 'G',
+            _ => 'T',
+        }).collect()
+    }
+
+    /// Collects all the elements into a `Vec`. The values are decoded (using `T::from_usize`)
+    /// one by one, so `T` needn't be `Clone`. For the other direction, use `from_slice` (or
+    /// `from_iter` for the values which aren't `Clone`).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(12, &[4095u16, 0, 2018]);
+    /// assert_eq!(vec.to_vec(), vec![4095, 0, 2018]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+
     /// Creates an iterator over the elements. Note that unlike other iterators, this gives the elements
     /// themselves, and not their references.
     #[inline]
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter { vec: self, range: 0..self.units }
     }
 
-    /// Creates an iterator consuming the vector.
+    /// Creates an iterator over the pairs of elements from this and the other vector. Like
+    /// other zipped iterators, this stops at the end of the shorter vector.
     #[inline]
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter { range: 0..self.units, vec: self }
+    pub fn zip<'a>(&'a self, other: &'a BitsVec<T>) -> Zip<Iter<'a, T>, Iter<'a, T>> {
+        self.iter().zip(other.iter())
+    }
+
+    /// Creates an iterator over every "N"th element, starting from the first one. Unlike
+    /// stepping over the output of `iter`, this only decodes the elements that are yielded.
+    /// Note that this panics when the step is zero.
+    #[inline]
+    pub fn iter_step(&self, step: usize) -> StepBy<Iter<'_, T>> {
+        assert!(step > 0, "[iter_step] step should be greater than zero");
+        self.iter().step_by(step)
+    }
+
+    /// Creates an iterator over the consecutive chunks (of the given size) of the vector, with
+    /// the last chunk possibly being shorter. The elements of each chunk are decoded into a `Vec`.
+    /// Note that this panics when the chunk size is zero (like `slice::chunks`).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(3, &[1u8, 2, 3, 4, 5]);
+    /// assert_eq!(vec.chunks(2).collect::<Vec<_>>(), vec![vec![1, 2], vec![3, 4], vec![5]]);
+    /// ```
+    pub fn chunks(&self, n: usize) -> Chunks<'_, T> {
+        assert!(n > 0, "[chunks] chunk size should be greater than zero");
+        Chunks { vec: self, start: 0, size: n }
+    }
+
+    /// Creates an iterator over all the windows of the given size (like the k-mers of a sequence),
+    /// advancing by one element at a time. The elements of each window are decoded into a `Vec`,
+    /// and there are no windows if the size is larger than the vector. Note that this panics
+    /// when the window size is zero (like `slice::windows`).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(2, &[0u8, 3, 1, 2]);
+    /// assert_eq!(vec.windows(3).collect::<Vec<_>>(), vec![vec![0, 3, 1], vec![3, 1, 2]]);
+    /// ```
+    pub fn windows(&self, n: usize) -> Windows<'_, T> {
+        assert!(n > 0, "[windows] window size should be greater than zero");
+        Windows { vec: self, start: 0, size: n }
+    }
+
+    /// Fold over the consecutive chunks (of the given size) of the vector, with the last chunk
+    /// possibly being shorter. The chunks are decoded one at a time into a reused buffer, so the
+    /// memory consumption remains flat for huge vectors. Note that this panics when the chunk
+    /// size is zero.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_iter(4, (0..10).map(|i| i as u8));
+    /// let sums = vec.fold_chunks(4, vec![], |mut sums, chunk| {
+    ///     sums.push(chunk.iter().map(|&i| i as usize).sum::<usize>());
+    ///     sums
+    /// });
+    /// assert_eq!(sums, vec![6, 22, 17]);
+    /// ```
+    pub fn fold_chunks<A, F>(&self, chunk: usize, init: A, mut f: F) -> A
+        where F: FnMut(A, &[T]) -> A
+    {
+        assert!(chunk > 0, "[fold_chunks] chunk size should be greater than zero");
+        let mut buffer = Vec::with_capacity(cmp::min(chunk, self.units));
+        let mut acc = init;
+        let mut iter = self.iter();
+        loop {
+            buffer.clear();
+            buffer.extend(iter.by_ref().take(chunk));
+            if buffer.is_empty() {
+                return acc
+            }
+
+            acc = f(acc, &buffer);
+        }
+    }
+
+    /// Get the value of the first "N" elements, read as the digits (most significant first)
+    /// of a number in base `2^bits`. This is the same as the packed bits of those elements,
+    /// which makes it a perfect hash for the short k-mers of a packed sequence. Note that the
+    /// digits are the packed values (i.e., two's complement within the bits, for the signed
+    /// vectors), and that this panics if there aren't enough elements, or if the value could
+    /// overflow an `u128`.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(2, &[1u8, 0, 3, 2]);
+    /// assert_eq!(vec.as_big_endian_value(3), 0b01_00_11);
+    /// ```
+    pub fn as_big_endian_value(&self, len: usize) -> u128 {
+        assert!(len <= self.units, "[as_big_endian_value] length exceeds the vector ({} > {})", len, self.units);
+        assert!(len * self.bits <= 128,
+                "[as_big_endian_value] value of {} elements overflows an u128 ({} bits)", len, len * self.bits);
+        (0..len).fold(0, |value, i| (value << self.bits) | self.read(i) as u128)
+    }
+
+    /// Get the value of the next window of "k" elements (as a number in base `2^bits`), given
+    /// the value of the window ending right before the element at the given index - i.e., drop
+    /// the most significant digit of the value, and shift in the element. This can be used for
+    /// rolling over all the k-mers in linear time, starting from the value of the first one
+    /// (see `as_big_endian_value`):
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(2, &[1u8, 0, 3, 2, 1]);
+    /// let first = vec.as_big_endian_value(3);
+    /// let second = vec.rolling_hash_step(first, 3, 3);
+    /// assert_eq!(second, 0b00_11_10);
+    /// assert_eq!(vec.rolling_hash_step(second, 3, 4), 0b11_10_01);
+    /// ```
+    ///
+    /// Note that this panics if the index is out of bounds, or if the window could overflow an `u128`.
+    pub fn rolling_hash_step(&self, value: u128, k: usize, i: usize) -> u128 {
+        assert!(i < self.units, "[rolling_hash_step] index out of bounds ({} >= {})", i, self.units);
+        assert!(k * self.bits <= 128,
+                "[rolling_hash_step] value of {} elements overflows an u128 ({} bits)", k, k * self.bits);
+        let mask = if k * self.bits == 128 { u128::MAX } else { (1 << (k * self.bits)) - 1 };
+        (value.checked_shl(self.bits as u32).unwrap_or(0) | self.read(i) as u128) & mask
     }
 }
 
@@ -340,6 +1652,24 @@ impl<T: ReprUsize + Clone> BitsVec<T> {
         vec
     }
 
+    /// Creates a vector that can hold the specified bits (atmost), packing the elements of the
+    /// given slice. Note that this panics if any of the elements can't be held by the vector.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(2, b"\x00\x02\x01\x03");
+    /// assert_eq!(vec.iter().collect::<Vec<_>>(), vec![0, 2, 1, 3]);
+    /// ```
+    pub fn from_slice(bits: usize, slice: &[T]) -> BitsVec<T> {
+        let mut vec = BitsVec::with_capacity(bits, slice.len());
+        for value in slice {
+            vec.push(value.clone());
+        }
+
+        vec
+    }
+
     /// Extends the vector to the specified length, filling additional values with the given element.
     /// Note that this method panics when the specified length is shorter than the initial length.
     pub fn extend_with_element(&mut self, length: usize, value: T) {
@@ -382,6 +1712,105 @@ impl<T: ReprUsize + Clone> BitsVec<T> {
             self.push(value.clone());
         }
     }
+
+    /// Resize the vector to the given length, either by truncating it, or by filling the
+    /// additional values with the given element.
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        if new_len < self.units {
+            self.truncate(new_len);
+        } else if new_len > self.units {
+            self.extend_with_element(new_len, value);
+        }
+    }
+
+    /// Overwrite the values starting from the given index with the values in the slice. All the
+    /// values are checked before writing, so this panics (leaving the vector untouched) when any
+    /// of them can't be held by the vector, or when the slice goes beyond the vector's length.
+    ///
+    /// The values are taken in their `usize` representation (see `ReprUsize::into_usize`). When
+    /// the slice starts at a word boundary, the values are packed into whole words, which are
+    /// written directly (instead of setting the values one by one).
+    pub fn set_slice(&mut self, start: usize, values: &[usize]) {
+        assert!(start + values.len() <= self.units,
+                "[set_slice] slice out of bounds ({} > {})", start + values.len(), self.units);
+        if let Some(&value) = values.iter().find(|&&v| self.encode(v).is_none()) {
+            self.out_of_range("set_slice", value);
+        }
+
+        let (bits, max_bits) = (self.bits, self.max_bits);
+        let pos = start * bits;
+        if !pos.is_multiple_of(max_bits) {
+            for (i, &value) in values.iter().enumerate() {
+                let packed = self.encode(value).unwrap();
+                self.write(start + i, packed);
+            }
+
+            return
+        }
+
+        let mut idx = pos / max_bits;
+        let (mut word, mut used) = (0, 0);
+        for &value in values {
+            let packed = self.encode(value).unwrap();
+            let free = max_bits - used;
+            if bits < free {
+                word |= packed << (free - bits);
+                used += bits;
+            } else {
+                let spill = bits - free;
+                self.inner[idx] = word | (packed >> spill);
+                idx += 1;
+                word = if spill == 0 { 0 } else { packed << (max_bits - spill) };
+                used = spill;
+            }
+        }
+
+        if used > 0 {   // the last word also has the elements following the slice
+            self.inner[idx] = word | (self.inner[idx] & ((1 << (max_bits - used)) - 1));
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: ReprUsize + Clone + Send + Sync> BitsVec<T> {
+    /// Creates a vector (like `with_elements`) with the words filled across multiple threads
+    /// (requires the `rayon` feature).
+    ///
+    /// The words holding a repeated value repeat themselves every `lcm(bits, max_bits)` bits,
+    /// so the words are filled in chunks of that period (which never split an element), and the
+    /// elements left over (less than a period) are pushed at the end.
+    pub fn par_with_elements(bits: usize, length: usize, value: T) -> BitsVec<T> {
+        let mut vec = BitsVec::new(bits);
+        let value = value.into_usize();
+        let packed = vec.encode(value).unwrap_or_else(|| vec.out_of_range("par_with_elements", value));
+        if bits == 0 {      // zero-bit elements don't need any words
+            vec.units = length;
+            return vec
+        }
+
+        let period = period_bits(bits, vec.max_bits);
+        let (period_units, period_words) = (period / bits, period / vec.max_bits);
+        let periods = length / period_units;
+        if periods > 0 {
+            let mut pattern = BitsVec::<T>::new(bits);
+            for _ in 0..period_units {
+                pattern.push_raw(packed);
+            }
+
+            let mut inner = vec![0; periods * period_words];
+            inner.par_chunks_mut(period_words).for_each(|words| words.copy_from_slice(&pattern.inner));
+            vec.inner = inner;
+            vec.units = periods * period_units;
+            vec.leftover = 0;
+        }
+
+        vec.reserve(length - vec.units);
+        while vec.units < length {
+            vec.push_raw(packed);
+        }
+
+        vec
+    }
 }
 
 impl<T: ReprUsize + PartialEq> BitsVec<T> {
@@ -390,105 +1819,706 @@ impl<T: ReprUsize + PartialEq> BitsVec<T> {
     pub fn contains(&self, element: &T) -> bool {
         self.iter().find(|ref i| i == &element).is_some()
     }
-}
 
-impl<T: ReprUsize + fmt::Debug> fmt::Debug for BitsVec<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_list().entries(self.iter()).finish()
+    /// Get the `(index, old, new)` values for the positions in which the other (new) vector
+    /// differs from this (old) vector. This is useful for delta-encoding the changes. Note that
+    /// this panics when the vectors have different lengths or bits.
+    pub fn diff(&self, other: &BitsVec<T>) -> Vec<(usize, T, T)> {
+        assert!(self.units == other.units && self.bits == other.bits,
+                "[diff] vectors should have the same length and bits");
+        (0..self.units).filter(|&i| self.read(i) != other.read(i))
+                       .map(|i| (i, self.get(i), other.get(i)))
+                       .collect()
     }
-}
 
-impl<T: ReprUsize> PartialEq for BitsVec<T> {
-    fn eq(&self, other: &Self) -> bool {
-        if self.units != other.units || self.bits != other.bits {
+    /// Checks whether the other vector is a cyclic rotation of this vector (useful for comparing
+    /// circular sequences). Both vectors should have the same length and bits.
+    ///
+    /// This searches for the other vector in this vector (doubled) in O(n) time, using the
+    /// [KMP algorithm](https://en.wikipedia.org/wiki/Knuth%E2%80%93Morris%E2%80%93Pratt_algorithm).
+    pub fn is_rotation_of(&self, other: &BitsVec<T>) -> bool {
+        if self.units != other.units || self.bits != other.bits || self.signed != other.signed {
             return false
         }
 
-        self.inner == other.inner
-    }
-}
+        let length = self.units;
+        let pattern = (0..length).map(|i| other.read(i)).collect::<Vec<_>>();
+        let mut failure = vec![0; length];
+        let mut k = 0;
+        for i in 1..length {
+            while k > 0 && pattern[i] != pattern[k] {
+                k = failure[k - 1];
+            }
 
-pub struct Iter<'a, T: ReprUsize + 'a> {
-    vec: &'a BitsVec<T>,
-    range: Range<usize>,
+            if pattern[i] == pattern[k] {
+                k += 1;
+            }
+
+            failure[i] = k;
+        }
+
+        k = 0;
+        for i in 0..(2 * length) {
+            let value = self.read(i % length);
+            while k > 0 && value != pattern[k] {
+                k = failure[k - 1];
+            }
+
+            if value == pattern[k] {
+                k += 1;
+            }
+
+            if k == length {
+                return true
+            }
+        }
+
+        length == 0
+    }
 }
 
-impl<'a, T: ReprUsize> IntoIterator for &'a BitsVec<T> {
-    type Item = T;
-    type IntoIter = Iter<'a, T>;
+impl<T: ReprUsize + Ord> BitsVec<T> {
+    /// Binary searches the (sorted) vector for the given element in O(log n) time, returning
+    /// `Ok` with its index if it's found (any of them, if there are many), or `Err` with the
+    /// index where it could be inserted (keeping the vector sorted), like `slice::binary_search`.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_slice(4, &[1u8, 3, 5, 7]);
+    /// assert_eq!(vec.binary_search(&5), Ok(2));
+    /// assert_eq!(vec.binary_search(&6), Err(3));
+    /// ```
+    pub fn binary_search(&self, value: &T) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.units);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.get(mid).cmp(value) {
+                cmp::Ordering::Less => low = mid + 1,
+                cmp::Ordering::Greater => high = mid,
+                cmp::Ordering::Equal => return Ok(mid),
+            }
+        }
 
-    fn into_iter(self) -> Iter<'a, T> {
-        self.iter()
+        Err(low)
     }
 }
 
-impl<'a, T: ReprUsize> Iterator for Iter<'a, T> {
-    type Item = T;
+impl BitsVec<bool> {
+    /// Creates a 1-bit vector (a bitset) from a slice of booleans.
+    pub fn from_bools(bits: &[bool]) -> BitsVec<bool> {
+        BitsVec::from_iter(1, bits.iter().cloned())
+    }
 
-    fn next(&mut self) -> Option<T> {
-        self.range.next().map(|i| self.vec.get(i))
+    /// Returns the number of `true` values in the vector. This counts the set bits in the
+    /// inner vector (ignoring the unused bits in the last word), which is a lot faster than
+    /// going through the elements.
+    pub fn count_ones(&self) -> usize {
+        let full = self.units / self.max_bits;
+        let used = self.units % self.max_bits;
+        let mut count = self.inner[..full].iter().map(|w| w.count_ones() as usize).sum();
+        if used > 0 {
+            let mask = !((1 << (self.max_bits - used)) - 1);
+            count += (self.inner[full] & mask).count_ones() as usize;
+        }
+
+        count
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.range.size_hint()
+    /// Converts a 1-bit vector into a `BitVec`.
+    ///
+    /// `BitVec` packs the bits into `u32` words (with the first bit in the least significant
+    /// position), so the words can't be transferred as such. Instead, the words are converted
+    /// to (and from) big-endian bytes, which is still a lot faster than going through the
+    /// elements. Note that this panics when the vector holds more than one bit per element.
+    pub fn into_bit_vec(self) -> BitVec {
+        assert!(self.bits == 1, "[into_bit_vec] vector should have 1-bit elements (found {})", self.bits);
+        let mut vec = BitVec::from_bytes(&self.packed_bytes());
+        vec.truncate(self.units);
+        vec
     }
 }
 
-impl<'a, T: ReprUsize> DoubleEndedIterator for Iter<'a, T> {
-    fn next_back(&mut self) -> Option<T> {
-        self.range.next_back().map(|i| self.vec.get(i))
+impl From<BitVec> for BitsVec<bool> {
+    /// Creates a 1-bit vector from a `BitVec` (see `into_bit_vec` for the reverse).
+    fn from(vec: BitVec) -> BitsVec<bool> {
+        BitsVec::from_packed_bytes(1, vec.len(), &vec.to_bytes())
     }
 }
 
-impl<'a, T: ReprUsize> ExactSizeIterator for Iter<'a, T> {}
-
-pub struct IntoIter<T: ReprUsize> {
-    vec: BitsVec<T>,
-    range: Range<usize>,
+/// A read-only view of the packed bits of a `BitsVec`, decoded as (`usize`) elements of
+/// some other width (see `BitsVec::view_as`).
+#[derive(Clone, Copy, Debug)]
+pub struct BitsVecView<'a> {
+    inner: &'a [usize],
+    units: usize,
+    bits: usize,
 }
 
-impl<T: ReprUsize> IntoIterator for BitsVec<T> {
-    type Item = T;
-    type IntoIter = IntoIter<T>;
+impl<'a> BitsVecView<'a> {
+    /// Get the number of elements in the view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.units
+    }
 
-    fn into_iter(self) -> IntoIter<T> {
-        self.into_iter()
+    /// Checks whether the view is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.units == 0
     }
-}
 
-impl<T: ReprUsize> Iterator for IntoIter<T> {
-    type Item = T;
+    /// Get the number of bits used by each element in the view.
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
 
-    fn next(&mut self) -> Option<T> {
-        self.range.next().map(|i| self.vec.get(i))
+    /// Get the element at an index in the view. Note that this panics when the index is out of bounds.
+    pub fn get(&self, i: usize) -> usize {
+        assert!(i < self.units, "[get] index out of bounds ({} >= {})", i, self.units);
+        read_packed(self.inner, self.bits, i)
     }
-}
 
-impl<T: ReprUsize> DoubleEndedIterator for IntoIter<T> {
-    fn next_back(&mut self) -> Option<T> {
-        self.range.next_back().map(|i| self.vec.get(i))
+    /// Returns `Some(value)` if the element exists at the given index or `None` if it doesn't.
+    pub fn checked_get(&self, i: usize) -> Option<usize> {
+        if i < self.units { Some(read_packed(self.inner, self.bits, i)) } else { None }
+    }
+
+    /// Creates an iterator over the elements of the view.
+    pub fn iter(&self) -> impl Iterator<Item=usize> + 'a {
+        let (inner, bits) = (self.inner, self.bits);
+        (0..self.units).map(move |i| read_packed(inner, bits, i))
     }
 }
 
-impl<T: ReprUsize> ExactSizeIterator for IntoIter<T> {}
+/// A builder for pushing a stream of elements into a `BitsVec`.
+///
+/// The elements are packed into a word (held by the builder), which is then flushed into the
+/// vector once it's full. This avoids going through the vector for every element, and so it's
+/// a little faster than repeatedly calling `push` (though it only supports pushing).
+///
+/// ``` rust
+/// use nucleic_acid::BitsVecBuilder;
+///
+/// let mut builder = BitsVecBuilder::new(22);
+/// for i in 0..1000 {
+///     builder.push(i * 100);
+/// }
+///
+/// let vec = builder.build();
+/// assert_eq!(vec.get(999), 99900);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BitsVecBuilder<T: ReprUsize> {
+    vec: BitsVec<T>,
+    /// packed bits which haven't been flushed (from the most significant bits)
+    word: usize,
+    /// number of bits used in the word
+    used: usize,
+}
 
-#[cfg(test)]
-mod tests {
-    use super::{BitsVec, ReprUsize};
-    use std::mem;
+impl<T: ReprUsize> BitsVecBuilder<T> {
+    /// Create a new builder for a vector that can hold values no larger than the specified `bits`.
+    pub fn new(bits: usize) -> BitsVecBuilder<T> {
+        BitsVecBuilder::with_capacity(bits, 0)
+    }
 
-    #[repr(usize)]
-    #[derive(Clone, Debug, Eq, PartialEq)]
-    enum TestEnum {
-        Value1,
-        Value2,
-        Value3,
-        Value4,
+    /// Create a new builder for a vector that can hold the specified bits (atmost) and has
+    /// capacity for "N" elements.
+    pub fn with_capacity(bits: usize, capacity: usize) -> BitsVecBuilder<T> {
+        let mut vec = BitsVec::with_capacity(bits, capacity);
+        vec.inner.clear();
+        BitsVecBuilder { vec, word: 0, used: 0 }
     }
 
-    impl ReprUsize for TestEnum {
+    /// Returns the number of elements pushed so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.vec.units
+    }
+
+    /// Checks whether any element has been pushed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.vec.units == 0
+    }
+
+    /// Push a value into the vector. Note that this panics (like `BitsVec::push`)
+    /// if the value is larger than what the vector can hold.
+    pub fn push(&mut self, value: T) {
+        let value = value.into_usize();
+        let (bits, max_bits) = (self.vec.bits, self.vec.max_bits);
+        let packed = self.vec.encode(value).unwrap_or_else(|| self.vec.out_of_range("push", value));
+
+        let free = max_bits - self.used;
+        if bits < free {
+            self.word |= packed << (free - bits);
+            self.used += bits;
+        } else {
+            let spill = bits - free;
+            self.vec.inner.push(self.word | (packed >> spill));
+            self.word = if spill == 0 { 0 } else { packed << (max_bits - spill) };
+            self.used = spill;
+        }
+
+        self.vec.units += 1;
+    }
+
+    /// Flush the remaining bits and get the vector.
+    pub fn build(mut self) -> BitsVec<T> {
+        let max_bits = self.vec.max_bits;
+        if self.used > 0 || self.vec.inner.is_empty() {
+            self.vec.inner.push(self.word);
+            self.vec.leftover = max_bits - self.used;
+        } else {
+            self.vec.leftover = 0;
+        }
+
+        self.vec
+    }
+}
+
+/// A builder for the sorted-delta encoding (see `BitsVec::from_sorted_deltas`) of a stream
+/// of non-decreasing values.
+///
+/// The builder remembers the last value, and pushes the differences into the vector (with the
+/// first value as such), so the stream needn't be collected beforehand. Unlike `from_sorted_deltas`,
+/// the bits should be chosen upfront, and the values which are out of order are rejected
+/// (instead of producing a corrupt stream of differences).
+///
+/// ``` rust
+/// use nucleic_acid::DeltaBuilder;
+///
+/// let mut builder = DeltaBuilder::new(10);
+/// builder.push_monotonic(1000).unwrap();
+/// builder.push_monotonic(1003).unwrap();
+/// assert!(builder.push_monotonic(1001).is_err());
+/// assert_eq!(builder.build().to_sorted_values(), vec![1000, 1003]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeltaBuilder {
+    builder: BitsVecBuilder<u64>,
+    last: Option<usize>,
+}
+
+impl DeltaBuilder {
+    /// Create a builder with the specified bits (for the differences).
+    ///
+    /// Note that the first value is stored as such, and so it should fit in these bits as well
+    /// (it'd be the first difference, from zero).
+    pub fn new(bits: usize) -> DeltaBuilder {
+        DeltaBuilder {
+            builder: BitsVecBuilder::new(bits),
+            last: None,
+        }
+    }
+
+    /// Get the number of values pushed so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    /// Checks whether any value has been pushed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.builder.is_empty()
+    }
+
+    /// Get the last value pushed into the builder (if any).
+    #[inline]
+    pub fn last(&self) -> Option<usize> {
+        self.last
+    }
+
+    /// Push the difference of the value from the last value, returning an error (without pushing)
+    /// if the value is smaller than the last one. Note that this panics (like `BitsVec::push`)
+    /// if the difference can't be held by the vector.
+    pub fn push_monotonic(&mut self, value: usize) -> Result<(), MonotonicError> {
+        let last = self.last.unwrap_or(0);
+        if value < last {
+            return Err(MonotonicError { value, last })
+        }
+
+        self.builder.push((value - last) as u64);
+        self.last = Some(value);
+        Ok(())
+    }
+
+    /// Get the vector of differences.
+    pub fn build(self) -> BitsVec<u64> {
+        self.builder.build()
+    }
+}
+
+impl BitsVec<u64> {
+    /// Creates a vector for a sorted (non-decreasing) sequence, holding its first value followed
+    /// by the differences between its consecutive values. The number of bits is chosen from the
+    /// largest of them, which packs a lot tighter than the values themselves for the sorted
+    /// lists of positions (like suffix array ranges). Note that this panics if the values aren't sorted.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_sorted_deltas(&[1000, 1003, 1010, 1012]);
+    /// assert_eq!(vec.bits(), 10);
+    /// assert_eq!(vec.iter().collect::<Vec<_>>(), vec![1000, 3, 7, 2]);
+    /// assert_eq!(vec.to_sorted_values(), vec![1000, 1003, 1010, 1012]);
+    /// ```
+    pub fn from_sorted_deltas(values: &[u64]) -> BitsVec<u64> {
+        let deltas = values.iter().enumerate().map(|(i, &v)| {
+            if i == 0 {
+                return v
+            }
+
+            assert!(v >= values[i - 1], "[from_sorted_deltas] values should be sorted ({} > {})", values[i - 1], v);
+            v - values[i - 1]
+        }).collect::<Vec<_>>();
+
+        let max = deltas.iter().cloned().max().unwrap_or(0);
+        BitsVec::from_slice(min_bits(max as usize), &deltas)
+    }
+
+    /// Get the sorted sequence back from a vector of differences (see `from_sorted_deltas`).
+    pub fn to_sorted_values(&self) -> Vec<u64> {
+        self.iter().scan(0, |sum, delta| {
+            *sum += delta;
+            Some(*sum)
+        }).collect()
+    }
+}
+
+impl<T: ReprUsize> Extend<T> for BitsVec<T> {
+    /// Push the elements of an iterator into the vector (which can also be another vector, by
+    /// reference). Note that this panics (like `push`) if any of the elements can't be held
+    /// by the vector. For the non-panicking version, use `try_extend`.
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iterable: I) {
+        let iter = iterable.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: ReprUsize> From<BitsVec<T>> for Vec<T> {
+    /// Unpack the vector into a `Vec` (see `BitsVec::to_vec`).
+    fn from(vec: BitsVec<T>) -> Vec<T> {
+        vec.to_vec()
+    }
+}
+
+impl<T: ReprUsize + fmt::Display> fmt::Display for BitsVec<T> {
+    /// Render the elements (comma-separated, within brackets), showing only the first and last
+    /// few elements of the long vectors. The number of elements shown at each end can be set
+    /// using the precision (it's 5 by default).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// let vec = BitsVec::from_iter(4, (0..20u8).map(|i| i % 16));
+    /// assert_eq!(format!("{}", vec), "[0, 1, 2, 3, 4, ..., 15, 0, 1, 2, 3]");
+    /// assert_eq!(format!("{:.2}", vec), "[0, 1, ..., 2, 3]");
+    /// assert_eq!(format!("{}", BitsVec::from_slice(4, &[1u8, 2])), "[1, 2]");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let edge = f.precision().unwrap_or(DISPLAY_EDGE);
+        let (head, tail) = if self.units > 2 * edge { (edge, self.units - edge) } else { (self.units, self.units) };
+        f.write_str("[")?;
+        for i in 0..head {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}", self.get(i))?;
+        }
+
+        if head < tail {
+            f.write_str(if head > 0 { ", ..." } else { "..." })?;
+            for i in tail..self.units {
+                write!(f, ", {}", self.get(i))?;
+            }
+        }
+
+        f.write_str("]")
+    }
+}
+
+impl<T: ReprUsize + fmt::Debug> fmt::Debug for BitsVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: ReprUsize> PartialEq for BitsVec<T> {
+    /// Compare the packed words holding the elements, ignoring the unused bits (and words)
+    /// at the end, which may have leftovers from the previous operations.
+    fn eq(&self, other: &Self) -> bool {
+        if self.units != other.units || self.bits != other.bits || self.signed != other.signed {
+            return false
+        }
+
+        self.significant_words().eq(other.significant_words())
+    }
+}
+
+impl<T: ReprUsize> Hash for BitsVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.units.hash(state);
+        self.bits.hash(state);
+        self.signed.hash(state);
+        for word in self.significant_words() {
+            word.hash(state);
+        }
+    }
+}
+
+impl<T: ReprUsize> Eq for BitsVec<T> {}
+
+impl<T: ReprUsize + Ord> PartialOrd for BitsVec<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ReprUsize + Ord> Ord for BitsVec<T> {
+    /// Compare the vectors lexicographically, by the values of their elements (so, a vector
+    /// sorts before its extensions). The vectors may have different bits, in which case the
+    /// elements are still compared by their values, and only the vectors with the same elements
+    /// are ordered by their bits (fewer first), and then by signedness (to stay consistent with `Eq`).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::BitsVec;
+    ///
+    /// assert!(BitsVec::from_slice(8, &[1u8, 2, 3]) < BitsVec::from_slice(3, &[1, 2, 4]));
+    /// assert!(BitsVec::from_slice(3, &[1u8, 2]) < BitsVec::from_slice(3, &[1, 2, 0]));
+    /// ```
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.iter().cmp(other.iter())
+                   .then(self.bits.cmp(&other.bits))
+                   .then(self.signed.cmp(&other.signed))
+    }
+}
+
+// The fields of a serialized `BitsVec`, which are checked before deserializing the vector.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawBitsVec {
+    inner: Vec<usize>,
+    units: usize,
+    bits: usize,
+    max_bits: usize,
+    leftover: usize,
+    signed: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: ReprUsize> Deserialize<'de> for BitsVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BitsVec<T>, D::Error> {
+        let raw = RawBitsVec::deserialize(deserializer)?;
+        let max = usize::MAX.count_ones() as usize;
+        if raw.max_bits != max {
+            return Err(D::Error::custom(format!("vector should have {} bits per word (found {})", max, raw.max_bits)))
+        } else if raw.bits >= max || (raw.signed && raw.bits == 0) {
+            return Err(D::Error::custom(format!("vector cannot hold {} bits at a time", raw.bits)))
+        }
+
+        // the words should be just enough for the elements (with an unused word at the end
+        // only when the elements end at a word boundary)
+        let bits = raw.units.checked_mul(raw.bits).ok_or_else(|| D::Error::custom("length overflows"))?;
+        let (words, used) = (bits.div_ceil(max), bits % max);
+        let consistent = match (used, raw.inner.len()) {
+            (0, len) if len == words + 1 => raw.leftover == max,
+            (0, len) if len == words => words > 0 && raw.leftover == 0,
+            (_, len) => len == words && raw.leftover == max - used,
+        };
+
+        if !consistent {
+            return Err(D::Error::custom(format!("words and leftover bits don't match the elements \
+                                                 ({} words, {} leftover for {} bits)", raw.inner.len(), raw.leftover, bits)))
+        }
+
+        // the unused bits are cleared, since the values are pushed by setting the bits
+        let mut inner = raw.inner;
+        if used > 0 {
+            inner[words - 1] &= !((1 << (max - used)) - 1);
+        } else if inner.len() > words {
+            inner[words] = 0;
+        }
+
+        Ok(BitsVec {
+            inner,
+            units: raw.units,
+            bits: raw.bits,
+            max_bits: raw.max_bits,
+            leftover: raw.leftover,
+            signed: raw.signed,
+            _marker: PhantomData,
+        })
+    }
+}
+
+pub struct Iter<'a, T: ReprUsize + 'a> {
+    vec: &'a BitsVec<T>,
+    range: Range<usize>,
+}
+
+impl<'a, T: ReprUsize> IntoIterator for &'a BitsVec<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: ReprUsize> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.range.next().map(|i| self.vec.get(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.range.nth(n).map(|i| self.vec.get(i))
+    }
+}
+
+impl<'a, T: ReprUsize> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.range.next_back().map(|i| self.vec.get(i))
+    }
+}
+
+impl<'a, T: ReprUsize> ExactSizeIterator for Iter<'a, T> {}
+
+pub struct IntoIter<T: ReprUsize> {
+    vec: BitsVec<T>,
+    range: Range<usize>,
+}
+
+impl<T: ReprUsize> IntoIterator for BitsVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    /// Creates an iterator consuming the vector.
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { range: 0..self.units, vec: self }
+    }
+}
+
+impl<T: ReprUsize> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.range.next().map(|i| self.vec.get(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.range.nth(n).map(|i| self.vec.get(i))
+    }
+}
+
+impl<T: ReprUsize> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.range.next_back().map(|i| self.vec.get(i))
+    }
+}
+
+impl<T: ReprUsize> ExactSizeIterator for IntoIter<T> {}
+
+pub struct Chunks<'a, T: ReprUsize + 'a> {
+    vec: &'a BitsVec<T>,
+    start: usize,
+    size: usize,
+}
+
+impl<'a, T: ReprUsize> Iterator for Chunks<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.start >= self.vec.units {
+            return None
+        }
+
+        let end = cmp::min(self.start.saturating_add(self.size), self.vec.units);
+        let chunk = (self.start..end).map(|i| self.vec.get(i)).collect();
+        self.start = end;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = (self.vec.units - self.start).div_ceil(self.size);
+        (count, Some(count))
+    }
+}
+
+impl<'a, T: ReprUsize> ExactSizeIterator for Chunks<'a, T> {}
+
+pub struct Windows<'a, T: ReprUsize + 'a> {
+    vec: &'a BitsVec<T>,
+    start: usize,
+    size: usize,
+}
+
+impl<'a, T: ReprUsize> Iterator for Windows<'a, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.size > self.vec.units - self.start {
+            return None
+        }
+
+        let window = (self.start..self.start + self.size).map(|i| self.vec.get(i)).collect();
+        self.start += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = (self.vec.units - self.start + 1).saturating_sub(self.size);
+        (count, Some(count))
+    }
+}
+
+impl<'a, T: ReprUsize> ExactSizeIterator for Windows<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitsVec, BitsVecBuilder, DeltaBuilder, LenError, MonotonicError, PushError, ReprUsize,
+                VarintError};
+    use bit_vec::BitVec;
+    #[cfg(feature = "serde")]
+    use serde_json;
+    use std::cmp;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::io;
+    use std::mem;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[repr(usize)]
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    enum TestEnum {
+        Value1,
+        Value2,
+        Value3,
+        Value4,
+    }
+
+    impl ReprUsize for TestEnum {
         fn into_usize(self) -> usize { self as usize }
         fn from_usize(i: usize) -> Self { unsafe { mem::transmute(i) } }
+        fn bit_width() -> Option<usize> { Some(2) }
     }
 
     #[test]
@@ -509,18 +2539,1300 @@ mod tests {
     }
 
     #[test]
-    fn test_truncate() {
-        let mut vec = BitsVec::with_elements(7, 50, 13);
-        vec.truncate(10);
-        assert_eq!(vec.inner_len(), 2);
-        assert_eq!(vec.get(9), 13);
-        vec.push(25);
-        assert_eq!(vec.get(10), 25);
-        let mut vec = BitsVec::with_elements(8, 20, 50);
-        vec.truncate(8);
-        assert_eq!(vec.inner_len(), 2);
-        assert_eq!(vec.get(7), 50);
-        vec.push(20);
-        assert_eq!(vec.get(8), 20);
+    fn test_checked_truncate() {
+        let mut vec = BitsVec::with_elements(5, 30, 17u8);
+        assert_eq!(vec.checked_truncate(31), Err(LenError { length: 31, actual: 30 }));
+        assert_eq!(vec.len(), 30);
+        assert_eq!(vec.checked_truncate(30), Ok(()));
+        assert_eq!(vec.checked_truncate(12), Ok(()));
+        assert_eq!(vec.len(), 12);
+        assert!(vec.iter().all(|v| v == 17));
+        vec.clear();
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_truncate_to_length() {
+        let mut vec = BitsVec::from_iter(8, 0..16u8);
+        let words = vec.as_words().to_vec();
+        vec.truncate(16);
+        assert_eq!(vec.as_words(), &words[..]);
+        vec.push(16);
+        assert!(vec.iter().eq(0..17));
+        vec.truncate(0);
+        assert!(vec.is_empty());
+        vec.truncate(0);
+        vec.clear();
+        assert_eq!(vec.as_words(), &[0]);
+        let mut vec = BitsVec::<u8>::new(3);
+        vec.clear();
+        vec.truncate(0);
+        vec.push(5);
+        assert_eq!(vec.get(0), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_truncate_too_long() {
+        BitsVec::with_elements(5, 30, 17u8).truncate(40);
+    }
+
+    #[test]
+    fn test_with_max_value() {
+        let mut vec = BitsVec::with_max_value(1000, 20);
+        assert_eq!(vec.bits(), 10);
+        vec.push(1000u16);
+        vec.push(1023);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![1000, 1023]);
+        assert_eq!(BitsVec::<u8>::with_max_value(0, 0).bits(), 1);
+        assert_eq!(BitsVec::<u8>::with_max_value(1024, 0).bits(), 11);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_max_value_overflow() {
+        BitsVec::with_max_value(1000, 0).push(1024u16);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let mut pushed = BitsVec::from_iter(8, 0..16u8);
+        let mut truncated = BitsVec::from_iter(8, 0..20u8);
+        truncated.truncate(16);
+        assert!(pushed.iter().eq(truncated.iter()));
+        pushed.canonicalize();
+        truncated.canonicalize();
+        assert_eq!(pushed.as_words(), truncated.as_words());
+        pushed.push(16);
+        assert_eq!(pushed.get(16), 16);
+
+        let mut vec = BitsVec::from_iter(5, (0..30).map(|i| i as u8));
+        let words = vec.as_words().to_vec();
+        vec.canonicalize();
+        assert_eq!(vec.as_words(), &words[..]);
+        vec.clear();
+        vec.canonicalize();
+        assert_eq!(vec.as_words(), &[0]);
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut vec = BitsVec::new(7);
+        for i in 0..1000 {
+            vec.push((i % 128) as u8);
+        }
+
+        let words = vec.as_words().to_vec();
+        assert_eq!(vec.pop(), Some((999 % 128) as u8));
+        vec.push((999 % 128) as u8);
+        assert_eq!(vec.as_words(), &words[..]);
+        for i in (0..1000).rev() {
+            assert_eq!(vec.pop(), Some((i % 128) as u8));
+        }
+
+        assert!(vec.is_empty());
+        assert_eq!(vec.inner_len(), 1);
+        assert_eq!(vec.pop(), None);
+        vec.push(5);
+        assert_eq!(vec.as_words(), BitsVec::from_iter(7, vec![5u8].into_iter()).as_words());
+    }
+
+    #[test]
+    fn test_append() {
+        let (a, b) = ((0..13).map(|i| i * 9).collect::<Vec<usize>>(), (0..9).map(|i| 127 - i).collect::<Vec<_>>());
+        let mut vec = BitsVec::from_slice(7, &a);
+        let mut other = BitsVec::from_slice(7, &b);
+        vec.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(vec.len(), 22);
+        assert!(vec.iter().eq(a.iter().chain(&b).cloned()));
+
+        // at the word boundary (both with and without an unused word)
+        let mut vec = BitsVec::from_iter(8, 0..16u8);
+        vec.append(&mut BitsVec::from_iter(8, 16..30));
+        assert!(vec.iter().eq(0..30));
+        vec.push(30);
+        vec.truncate(24);
+        vec.append(&mut BitsVec::from_iter(8, 24..101));
+        assert!(vec.iter().eq(0..101));
+        vec.push(101);
+        assert_eq!(vec, BitsVec::from_iter(8, 0..102));
+
+        let mut vec = BitsVec::new(8);
+        vec.append(&mut BitsVec::from_iter(8, 0..3u8));
+        vec.append(&mut BitsVec::new(8));
+        assert_eq!(vec, BitsVec::from_iter(8, 0..3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_append_different_bits() {
+        BitsVec::from_iter(7, 0..3u8).append(&mut BitsVec::from_iter(8, 0..3));
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut expected = (0..60).map(|i| i % 8).collect::<Vec<usize>>();
+        let mut vec = BitsVec::from_slice(3, &expected);
+        for &(i, value) in &[(0, 7), (30, 1), (62, 5), (63, 6), (21, 0), (1, 3)] {
+            vec.insert(i, value);
+            expected.insert(i, value);
+            assert_eq!(vec.iter().collect::<Vec<_>>(), expected);
+        }
+
+        let mut vec = BitsVec::new(3);
+        vec.insert(0, 4u8);
+        vec.insert(0, 2);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_overflow() {
+        BitsVec::with_elements(3, 10, 3u8).insert(4, 8);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut expected = (0..200).map(|i| (i * 37) % 128).collect::<Vec<usize>>();
+        let mut vec = BitsVec::from_slice(7, &expected);
+        for &i in &[0, 100, 197, 50, 0, 194, 63] {
+            assert_eq!(vec.remove(i), expected.remove(i));
+            assert_eq!(vec.len(), expected.len());
+            assert_eq!(vec.iter().collect::<Vec<_>>(), expected);
+        }
+
+        vec.push(127);
+        expected.push(127);
+        assert_eq!(vec, BitsVec::from_slice(7, &expected));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_remove_out_of_bounds() {
+        BitsVec::with_elements(7, 10, 3u8).remove(10);
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut vec = BitsVec::with_elements(9, 100, 300u16);
+        vec.resize(100, 0);
+        assert_eq!(vec, BitsVec::with_elements(9, 100, 300));
+        vec.resize(40, 0);
+        assert_eq!(vec, BitsVec::with_elements(9, 40, 300));
+        vec.resize(250, 17);
+        assert_eq!(vec.len(), 250);
+        assert!(vec.iter().enumerate().all(|(i, v)| v == if i < 40 { 300 } else { 17 }));
+        vec.resize(0, 17);
+        assert!(vec.is_empty());
+        vec.resize(5, 511);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![511; 5]);
+    }
+
+    #[test]
+    fn test_resize_with() {
+        let mut vec = BitsVec::new(3);
+        vec.resize_with(100, |i| (i % 8) as u8);
+        assert_eq!(vec.len(), 100);
+        assert!(vec.iter().enumerate().all(|(i, v)| v as usize == i % 8));
+        vec.resize_with(30, |_| unreachable!());
+        assert_eq!(vec.len(), 30);
+        vec.resize_with(40, |i| (7 - i % 8) as u8);
+        assert!(vec.iter().skip(30).enumerate().all(|(i, v)| v as usize == 7 - (i + 30) % 8));
+        assert!(vec.iter().take(30).enumerate().all(|(i, v)| v as usize == i % 8));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_resize_with_overflow() {
+        BitsVec::new(3).resize_with(10, |i| i as u8);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut vec = BitsVec::with_elements(7, 50, 13);
+        vec.truncate(10);
+        assert_eq!(vec.inner_len(), 2);
+        assert_eq!(vec.get(9), 13);
+        vec.push(25);
+        assert_eq!(vec.get(10), 25);
+        let mut vec = BitsVec::with_elements(8, 20, 50);
+        vec.truncate(8);
+        assert_eq!(vec.inner_len(), 2);
+        assert_eq!(vec.get(7), 50);
+        vec.push(20);
+        assert_eq!(vec.get(8), 20);
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let values = (0..300).map(|i| (i * 5 % 8) as u8).collect::<Vec<_>>();
+        let vec = BitsVec::from_slice(3, &values);
+        assert_eq!(vec.len(), 300);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), values);
+        assert_eq!(vec, BitsVec::from_iter(3, values.into_iter()));
+        assert!(BitsVec::<u8>::from_slice(3, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_get_unchecked() {
+        for &bits in &[1, 7, 22, 63] {
+            let max = (1usize << bits) - 1;
+            let vec = BitsVec::from_iter(bits, (0..300).map(|i| (i * 7919) & max));
+            assert!((0..vec.len()).all(|i| unsafe { vec.get_unchecked(i) } == vec.get(i)));
+        }
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let vec = Arc::new(BitsVec::from_iter(22, (0..10000).map(|i| i * 401)));
+        let handles = (0..4).map(|t| {
+            let vec = vec.clone();
+            thread::spawn(move || (t..vec.len()).step_by(4).all(|i| vec.get(i) == i * 401))
+        }).collect::<Vec<_>>();
+
+        assert!(handles.into_iter().all(|h| h.join().unwrap()));
+    }
+
+    #[test]
+    fn test_set_slice() {
+        let mut vec = BitsVec::with_elements(11, 100, 7u16);
+        let window = (0..30).map(|i| i * 60).collect::<Vec<_>>();
+        vec.set_slice(40, &window);
+        assert!(vec.iter().skip(40).take(30).map(|v| v as usize).eq(window.iter().cloned()));
+        assert!(vec.iter().take(40).all(|v| v == 7));
+        assert!(vec.iter().skip(70).all(|v| v == 7));
+        vec.set_slice(95, &[1, 2, 3, 4, 5]);
+        assert_eq!(vec.get(99), 5);
+        vec.set_slice(100, &[]);
+
+        // slices starting at the word boundaries (ending anywhere)
+        for &(bits, start) in &[(11, 64), (16, 4), (16, 8), (1, 128), (63, 64), (32, 2)] {
+            let mut vec = BitsVec::with_elements(bits, 200, 1u64);
+            for len in 0..(200 - start) {
+                let mut expected = vec.clone();
+                let window = (0..len).map(|i| (i * 37) % (1 << bits)).collect::<Vec<_>>();
+                for (i, &v) in window.iter().enumerate() {
+                    expected.set(start + i, v as u64);
+                }
+
+                vec.set_slice(start, &window);
+                assert_eq!(vec, expected);
+            }
+        }
+
+        let mut vec = BitsVec::new_signed(5);
+        vec.resize(20, 0i8);
+        vec.set_slice(0, &[-3i8 as usize, 15, -16i8 as usize]);
+        assert_eq!(vec.iter().take(4).collect::<Vec<_>>(), vec![-3, 15, -16, 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_slice_out_of_bounds() {
+        BitsVec::with_elements(5, 10, 1u8).set_slice(8, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_view_as() {
+        let vec = BitsVec::from_iter(16, (0..100).map(|i| (i * 653) as u16));
+        let view = vec.view_as(8);
+        assert_eq!(view.len(), 200);
+        for i in 0..100 {
+            let value = (i * 653) as u16 as usize;
+            assert_eq!(view.get(2 * i), value >> 8);
+            assert_eq!(view.get(2 * i + 1), value & 0xff);
+        }
+
+        assert!(view.checked_get(200).is_none());
+        assert!(vec.view_as(32).iter().eq(vec.iter().collect::<Vec<_>>()
+                                             .chunks(2).map(|c| ((c[0] as usize) << 16) | c[1] as usize)));
+        let vec = BitsVec::from_iter(3, vec![5u8, 2, 7, 1].into_iter());
+        assert_eq!(vec.view_as(4).iter().collect::<Vec<_>>(), vec![0b1010, 0b1011, 0b1001]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_view_as_invalid_bits() {
+        BitsVec::from_iter(3, vec![5u8, 2, 7].into_iter()).view_as(2);
+    }
+
+    #[test]
+    fn test_builder() {
+        for &bits in &[1, 3, 8, 22, 32, 40, 63] {
+            let max = (1usize << bits) - 1;
+            for &length in &[0, 1, 64, 100, 1000] {
+                let mut builder = BitsVecBuilder::with_capacity(bits, length);
+                for i in 0..length {
+                    builder.push((i * 7919) & max);
+                }
+
+                assert_eq!(builder.len(), length);
+                let mut vec = builder.build();
+                let expected = BitsVec::from_iter(bits, (0..length).map(|i| (i * 7919) & max));
+                assert_eq!(vec, expected);
+                vec.push(max);
+                assert_eq!(vec.get(length), max);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_builder_overflow() {
+        BitsVecBuilder::new(4).push(16u8);
+    }
+
+    #[test]
+    fn test_drain_front() {
+        let mut vec = BitsVec::from_iter(6, (0..40).map(|i| i as u8));
+        let drained = vec.drain_front(15);
+        assert_eq!(drained, (0..15).collect::<Vec<_>>());
+        assert_eq!(vec.len(), 25);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), (15..40).collect::<Vec<_>>());
+        vec.push(63);
+        assert_eq!(vec.get(25), 63);
+        assert!(vec.drain_front(0).is_empty());
+        assert_eq!(vec.drain_front(26).len(), 26);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_iter_step() {
+        let vec = BitsVec::from_iter(5, (0..12).map(|i| i * 2));
+        assert_eq!(vec.iter_step(3).collect::<Vec<_>>(), vec![0, 6, 12, 18]);
+        assert_eq!(vec.iter_step(5).collect::<Vec<_>>(), vec![0, 10, 20]);
+        assert_eq!(vec.iter_step(20).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn test_fold_chunks() {
+        let vec = BitsVec::from_iter(10, (0..1000).map(|i| (i * 7) % 1024));
+        let total = vec.iter().map(|i: usize| i).sum::<usize>();
+        for &chunk in &[1, 7, 64, 999, 1000, 5000] {
+            let (sum, count) = vec.fold_chunks(chunk, (0, 0), |(sum, count), values| {
+                assert!(values.len() <= chunk);
+                (sum + values.iter().sum::<usize>(), count + 1)
+            });
+            assert_eq!(sum, total);
+            assert_eq!(count, 1000usize.div_ceil(chunk));
+        }
+
+        assert_eq!(BitsVec::<u8>::new(3).fold_chunks(4, 0, |n, _| n + 1), 0);
+    }
+
+    #[test]
+    fn test_from_iter_auto() {
+        let vec = BitsVec::from_iter_auto((0..1001).map(|i| i as u16));
+        assert_eq!(vec.bits(), 10);
+        assert_eq!(vec.len(), 1001);
+        assert!(vec.iter().eq(0..1001));
+        assert_eq!(BitsVec::from_iter_auto(vec![1u8, 0, 1].into_iter()).bits(), 1);
+        let vec = BitsVec::<u8>::from_iter_auto(Vec::new().into_iter());
+        assert!(vec.is_empty());
+        assert_eq!(vec.bits(), 1);
+    }
+
+    #[test]
+    fn test_try_from_iter() {
+        let vec = BitsVec::try_from_iter(13, 0..5000usize).unwrap();
+        assert_eq!(vec, BitsVec::from_iter(13, 0..5000));
+        assert!(vec.iter().eq(0..5000));
+        let result = BitsVec::try_from_iter(4, vec![3, 15, 16, 2].into_iter());
+        assert_eq!(result, Err(PushError { value: 16, bits: 4 }));
+    }
+
+    #[test]
+    fn test_for_each_mut() {
+        let values = (0..250).map(|i| (i * 13) % 63).collect::<Vec<usize>>();
+        let mut vec = BitsVec::from_slice(6, &values);
+        let mut expected = vec.clone();
+        vec.for_each_mut(|x| x + 1);
+        for i in 0..expected.len() {
+            let value = expected.get(i);
+            expected.set(i, value + 1);
+        }
+
+        assert_eq!(vec, expected);
+        assert!(vec.iter().zip(&values).all(|(a, &b)| a == b + 1));
+
+        // elements ending at the word boundaries (and filling the last word), wide and signed elements
+        for &(bits, len) in &[(8, 64), (32, 7), (63, 9), (1, 130)] {
+            let mut vec = BitsVec::from_iter(bits, (0..len).map(|i| i % 2));
+            vec.for_each_mut(|x| 1 - x);
+            assert!(vec.iter().eq((0..len).map(|i| 1 - i % 2)));
+            vec.push(1);
+            assert_eq!(vec.get(len), 1);
+        }
+
+        let mut vec = BitsVec::new_signed(7);
+        vec.extend((-64..64).map(|i| i as i8));
+        vec.for_each_mut(|x| -(x / 2));
+        assert!(vec.iter().eq((-64..64).map(|i| -(i / 2) as i8)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_for_each_mut_overflow() {
+        BitsVec::from_iter(6, (0..64).map(|i| i as u8)).for_each_mut(|x| x + 1);
+    }
+
+    #[test]
+    fn test_apply_permutation() {
+        let values = (0..100).map(|i| (i * 7) % 32).collect::<Vec<usize>>();
+        let mut vec = BitsVec::from_slice(5, &values);
+        let perm = (0..100).map(|i| (i * 37 + 11) % 100).collect::<Vec<_>>();
+        vec.apply_permutation(&perm);
+        assert!(vec.iter().eq(perm.iter().map(|&i| values[i])));
+
+        // argsort
+        let mut vec = BitsVec::from_slice(5, &values);
+        let mut perm = (0..100).collect::<Vec<_>>();
+        perm.sort_by_key(|&i| values[i]);
+        vec.apply_permutation(&perm);
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(vec.iter().collect::<Vec<_>>(), sorted);
+
+        // fixed points and swaps
+        let mut vec = BitsVec::from_iter(7, 0..6u8);
+        vec.apply_permutation(&[0, 2, 1, 3, 5, 4]);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![0, 2, 1, 3, 5, 4]);
+        BitsVec::<u8>::new(7).apply_permutation(&[]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_apply_permutation_invalid() {
+        BitsVec::from_iter(5, 0..4u8).apply_permutation(&[0, 1, 1, 3]);
+    }
+
+    #[test]
+    fn test_fill_range() {
+        let mut vec = BitsVec::with_elements(4, 100, 3u8);
+        vec.fill_range(10..90, 12);
+        assert!(vec.iter().enumerate().all(|(i, v)| v == if (10..90).contains(&i) { 12 } else { 3 }));
+        for &bits in &[1, 5, 7, 16, 22, 40, 63] {
+            let max = (1usize << bits) - 1;
+            let values = (0..700).map(|i| (i * 7919) & max).collect::<Vec<_>>();
+            for &(start, end) in &[(0, 700), (3, 697), (64, 65), (100, 100), (1, 500)] {
+                let mut vec = BitsVec::from_slice(bits, &values);
+                vec.fill_range(start..end, max);
+                let mut expected = values.clone();
+                expected[start..end].iter_mut().for_each(|v| *v = max);
+                assert_eq!(vec.iter().collect::<Vec<_>>(), expected);
+            }
+        }
+
+        // zero-bit elements
+        let mut vec = unsafe { BitsVec::<u8>::from_raw_parts(vec![0], 0, 100) };
+        vec.fill_range(10..90, 0);
+        assert_eq!((vec.len(), vec.as_words()), (100, &[0][..]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fill_range_out_of_bounds() {
+        BitsVec::with_elements(4, 100, 3u8).fill_range(90..101, 1);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut values = (0..150).map(|i| (i * 11) % 32).collect::<Vec<usize>>();
+        let mut vec = BitsVec::from_slice(5, &values);
+        let mut state = 7usize;
+        for _ in 0..500 {
+            state = (state * 31 + 17) % 1009;
+            let (i, j) = (state % 150, (state / 7) % 150);
+            vec.swap(i, j);
+            values.swap(i, j);
+        }
+
+        vec.swap(7, 7);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), values);
+        let mut sorted = vec.iter().collect::<Vec<_>>();
+        let mut expected = (0..150).map(|i| (i * 11) % 32).collect::<Vec<_>>();
+        sorted.sort();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn test_saturating_arithmetic() {
+        let mut vec = BitsVec::with_elements(4, 20, 13u8);
+        vec.saturating_add_at(5, 1);
+        assert_eq!(vec.get(5), 14);
+        vec.saturating_add_at(5, 7);
+        assert_eq!(vec.get(5), 15);
+        vec.saturating_add_at(5, usize::MAX);
+        assert_eq!(vec.get(5), 15);
+        vec.saturating_sub_at(6, 10);
+        assert_eq!(vec.get(6), 3);
+        vec.saturating_sub_at(6, 10);
+        assert_eq!(vec.get(6), 0);
+        assert!(vec.iter().enumerate().all(|(i, v)| i == 5 || i == 6 || v == 13));
+    }
+
+    #[test]
+    fn test_extend_from_reader() {
+        let values = (0..500u32).map(|i| i * 131).collect::<Vec<_>>();
+        let bytes = values.iter().flat_map(|v| vec![*v as u8, (v >> 8) as u8, (v >> 16) as u8])
+                          .collect::<Vec<_>>();
+        let mut vec = BitsVec::new(17);
+        vec.push(3u32);
+        vec.extend_from_reader(io::Cursor::new(&bytes), 500, 3).unwrap();
+        assert_eq!(vec.len(), 501);
+        assert!(vec.iter().skip(1).eq(values.iter().cloned()));
+
+        let mut vec = BitsVec::new(7);
+        let err = vec.extend_from_reader(&[3, 127, 128, 5][..], 4, 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(vec.iter().collect::<Vec<u8>>(), vec![3, 127]);
+        let err = vec.extend_from_reader(&[1, 0, 3][..], 2, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut vec = BitsVec::new_signed(9);
+        vec.extend_from_reader(&[0x00, 0xff, 0xff, 0x00][..], 2, 2).unwrap();
+        assert_eq!(vec.iter().collect::<Vec<i16>>(), vec![-256, 255]);
+    }
+
+    #[test]
+    fn test_sorted_deltas() {
+        let values = (0..1000u64).map(|i| 3_000_000_000 + i * 17 + (i * i) % 13).collect::<Vec<_>>();
+        let vec = BitsVec::from_sorted_deltas(&values);
+        assert_eq!(vec.to_sorted_values(), values);
+        assert_eq!(vec.bits(), 32);
+
+        let positions = (0..1000u64).map(|i| i * 40 + i % 7).collect::<Vec<_>>();
+        let vec = BitsVec::from_sorted_deltas(&positions);
+        assert_eq!(vec.to_sorted_values(), positions);
+        assert_eq!(vec.bits(), 6);
+        assert!(vec.inner_len() < BitsVec::from_slice(16, &positions).inner_len() / 2);
+        assert!(BitsVec::from_sorted_deltas(&[]).to_sorted_values().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sorted_deltas_unsorted() {
+        BitsVec::from_sorted_deltas(&[3, 5, 4]);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut vec = BitsVec::from_iter(10, (0..20).map(|i| i * 3));
+        vec.extend((0..500).map(|i| (i * 7) % 1024));
+        assert_eq!(vec.len(), 520);
+        for i in 0..520 {
+            let expected = if i < 20 { i * 3 } else { ((i - 20) * 7) % 1024 };
+            assert_eq!(vec.get(i), expected);
+        }
+
+        let other = BitsVec::from_iter(10, 1000..1010);
+        vec.extend(&other);
+        assert!(vec.iter().skip(520).eq(1000..1010));
+        let mut pushed = BitsVec::new(10);
+        for value in vec.iter() {
+            pushed.push(value);
+        }
+
+        assert_eq!(vec, pushed);
+    }
+
+    #[test]
+    fn test_try_extend() {
+        let mut vec = BitsVec::from_iter(3, vec![1u8, 2].into_iter());
+        let result = vec.try_extend(vec![7, 6, 9, 4].into_iter());
+        assert_eq!(result, Err(PushError { value: 9, bits: 3 }));
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![1, 2, 7, 6]);
+        assert_eq!(vec.try_extend(vec![0, 5].into_iter()), Ok(()));
+        assert_eq!(vec.len(), 6);
+    }
+
+    #[test]
+    fn test_zip() {
+        let first = BitsVec::from_iter(4, 0..10u8);
+        let second = BitsVec::from_iter(4, (0..6u8).map(|i| 15 - i));
+        let pairs = first.zip(&second).collect::<Vec<_>>();
+        assert_eq!(pairs.len(), 6);
+        assert_eq!(pairs[0], (0, 15));
+        assert_eq!(pairs[5], (5, 10));
+        assert_eq!(second.zip(&first).count(), 6);
+    }
+
+    #[test]
+    fn test_fmt_dna() {
+        let vec = BitsVec::from_iter(2, vec![0u8, 1, 2, 3].into_iter());
+        assert_eq!(vec.fmt_dna(), "ACGT");
+        let vec = BitsVec::from_iter(2, (0..100).map(|i| i % 4));
+        assert_eq!(vec.fmt_dna(), "ACGT".repeat(25));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fmt_dna_invalid_bits() {
+        BitsVec::with_elements(3, 10, 0).fmt_dna();
+    }
+
+    #[test]
+    fn test_bools() {
+        let bools = (0..200).map(|i| i % 3 == 0 || i % 7 == 0).collect::<Vec<_>>();
+        let mut vec = BitsVec::from_bools(&bools);
+        assert_eq!(vec.len(), 200);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), bools);
+        assert_eq!(vec.count_ones(), bools.iter().filter(|&&b| b).count());
+        vec.truncate(70);
+        assert_eq!(vec.count_ones(), bools[..70].iter().filter(|&&b| b).count());
+        assert_eq!(BitsVec::from_bools(&[]).count_ones(), 0);
+    }
+
+    #[test]
+    fn test_bit_vec_round_trip() {
+        for &length in &[0, 1, 63, 64, 65, 200] {
+            let bits = BitVec::from_fn(length, |i| i % 3 == 0 || i % 5 == 1);
+            let mut vec = BitsVec::from(bits.clone());
+            assert_eq!(vec.len(), length);
+            assert!(vec.iter().eq(bits.iter()));
+            assert_eq!(vec.clone().into_bit_vec(), bits);
+            vec.push(true);
+            assert!(vec.get(length));
+        }
+    }
+
+    #[test]
+    fn test_for_enum() {
+        let mut vec = BitsVec::for_enum();
+        assert_eq!(vec.bits(), 2);
+        vec.push(TestEnum::Value4);
+        vec.push(TestEnum::Value2);
+        assert_eq!(vec.get(0), TestEnum::Value4);
+        assert_eq!(vec.get(1), TestEnum::Value2);
+        assert_eq!(BitsVec::<bool>::for_enum().bits(), 1);
+        assert_eq!(BitsVec::<u8>::for_enum().bits(), 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_for_enum_unknown_width() {
+        BitsVec::<usize>::for_enum();
+    }
+
+    #[test]
+    fn test_le_words() {
+        let vec = BitsVec::from_iter(13, (0..333).map(|i| ((i * 997) % 8192) as u16));
+        let words = vec.to_le_words();
+        assert_eq!(words.len(), (333 * 13usize).div_ceil(64));
+        assert_eq!(BitsVec::from_le_words(13, 333, &words), vec);
+        // the raw bytes (as written to a file) are little-endian on any host
+        let bytes = words.iter().flat_map(|w| w.to_ne_bytes().to_vec()).collect::<Vec<_>>();
+        let mut first = [0; 8];
+        first.copy_from_slice(&bytes[..8]);
+        let expected = vec.iter().take(4).fold(0u64, |word, v| (word << 13) | v as u64);
+        assert_eq!(u64::from_le_bytes(first) >> 12, expected);
+        // a buffer of the (native) words written by a big-endian host has the bytes swapped,
+        // and it can be recovered by reading the words in that byte order
+        let swapped = words.iter().flat_map(|w| u64::from_le(*w).to_be_bytes().to_vec()).collect::<Vec<_>>();
+        assert!(swapped != bytes);
+        let recovered = swapped.chunks(8).map(|c| {
+            let mut word = [0; 8];
+            word.copy_from_slice(c);
+            u64::from_be_bytes(word).to_le()
+        }).collect::<Vec<_>>();
+        assert!(BitsVec::<u16>::from_le_words(13, 333, &recovered).iter().eq(vec.iter()));
+        assert!(BitsVec::<u8>::from_le_words(5, 0, &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_le_words_too_short() {
+        BitsVec::<u8>::from_le_words(5, 13, &[0]);
+    }
+
+    #[test]
+    fn test_varint_bytes() {
+        let values = vec![0usize, 1, 127, 128, 5, 300, 16383, 16384, 2, 1 << 40];
+        let vec = BitsVec::from_iter(41, values.clone().into_iter());
+        let bytes = vec.to_varint_bytes();
+        assert_eq!(bytes.len(), 1 + 1 + 1 + 2 + 1 + 2 + 2 + 3 + 1 + 6);
+        let decoded = BitsVec::<usize>::from_varint_bytes(&bytes).unwrap();
+        assert_eq!(decoded.bits(), 41);
+        assert_eq!(decoded.iter().collect::<Vec<_>>(), values);
+        let small = BitsVec::<u8>::from_varint_bytes(&[3, 0, 7, 1]).unwrap();
+        assert_eq!(small.bits(), 3);
+        assert_eq!(small.iter().collect::<Vec<_>>(), vec![3, 0, 7, 1]);
+        assert!(BitsVec::<u8>::from_varint_bytes(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_varint_bytes_signed() {
+        let values = vec![0i64, -1, 1, -64, 64, -300, 1 << 40, -(1 << 40), 2];
+        let mut vec = BitsVec::new_signed(42);
+        vec.extend(values.clone());
+        let bytes = vec.to_varint_bytes();
+        assert_eq!(&bytes[..5], &[0, 1, 2, 127, 0x80]);
+        let decoded = BitsVec::<i64>::from_varint_bytes_signed(&bytes).unwrap();
+        assert!(decoded.is_signed());
+        assert_eq!(decoded.bits(), 42);
+        assert_eq!(decoded.iter().collect::<Vec<_>>(), values);
+        let small = BitsVec::<i8>::from_varint_bytes_signed(&[3, 0, 4, 1]).unwrap();
+        assert_eq!(small.bits(), 3);
+        assert_eq!(small.iter().collect::<Vec<_>>(), vec![-2, 0, 2, -1]);
+        assert!(BitsVec::<i8>::from_varint_bytes_signed(&[]).unwrap().is_empty());
+        assert_eq!(BitsVec::<i8>::from_varint_bytes_signed(&[3, 0x85]), Err(VarintError { position: 1 }));
+    }
+
+    #[test]
+    fn test_varint_bytes_invalid() {
+        let max_bits = usize::MAX.count_ones() as usize;
+        assert_eq!(BitsVec::<usize>::from_varint_bytes(&[3, 0x85]), Err(VarintError { position: 1 }));
+        // continuation bytes beyond the width of `usize`
+        let mut bytes = vec![1];
+        bytes.extend(vec![0x80; 20]);
+        bytes.push(1);
+        assert_eq!(BitsVec::<usize>::from_varint_bytes(&bytes), Err(VarintError { position: 1 }));
+
+        // the largest value that fits (in one bit less than `usize`), and the one after it
+        let largest = BitsVec::from_slice(max_bits - 1, &[usize::MAX >> 1]).to_varint_bytes();
+        let decoded = BitsVec::<usize>::from_varint_bytes(&largest).unwrap();
+        assert_eq!((decoded.bits(), decoded.get(0)), (max_bits - 1, usize::MAX >> 1));
+        let mut bytes = vec![0x80; (max_bits - 1) / 7];
+        bytes.push(1 << ((max_bits - 1) % 7));
+        assert_eq!(BitsVec::<usize>::from_varint_bytes(&bytes), Err(VarintError { position: 0 }));
+        let mut bytes = largest;
+        *bytes.last_mut().unwrap() |= 0x80;
+        bytes.push(2);
+        assert_eq!(BitsVec::<usize>::from_varint_bytes(&bytes), Err(VarintError { position: 0 }));
+    }
+
+    #[test]
+    fn test_is_rotation_of() {
+        let values = vec![3u8, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        let vec = BitsVec::from_iter(4, values.clone().into_iter());
+        for shift in 0..values.len() {
+            let rotated = values[shift..].iter().chain(&values[..shift]).cloned();
+            assert!(vec.is_rotation_of(&BitsVec::from_iter(4, rotated)));
+        }
+
+        let mut other = values.clone();
+        other.swap(0, 1);
+        assert!(!vec.is_rotation_of(&BitsVec::from_iter(4, other.into_iter())));
+        assert!(!vec.is_rotation_of(&BitsVec::from_iter(5, values.clone().into_iter())));
+        assert!(!vec.is_rotation_of(&BitsVec::from_iter(4, values[1..].iter().cloned())));
+        assert!(BitsVec::<u8>::new(4).is_rotation_of(&BitsVec::new(4)));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = BitsVec::from_iter(5, (0..30u8).map(|i| i % 20));
+        let mut new = old.clone();
+        assert!(old.diff(&new).is_empty());
+        new.set(0, 31);
+        new.set(17, 2);
+        new.set(29, 0);
+        assert_eq!(old.diff(&new), vec![(0, 0, 31), (17, 17, 2), (29, 9, 0)]);
+        assert_eq!(new.diff(&old), vec![(0, 31, 0), (17, 2, 17), (29, 0, 9)]);
+    }
+
+    #[test]
+    fn test_signed() {
+        let mut vec = BitsVec::new_signed(5);
+        for i in -16..16i8 {
+            vec.push(i);
+        }
+
+        assert_eq!(vec.iter().collect::<Vec<_>>(), (-16..16).collect::<Vec<_>>());
+        assert_eq!(vec.as_big_endian_value(3), 0b10000_10001_10010);     // not biased
+        vec.set(3, 15);
+        vec.set(4, -3);
+        assert_eq!(vec.get(3), 15);
+        assert_eq!(vec.get(4), -3);
+        assert!(vec.try_push(16).is_err());
+        assert!(vec.try_push(-17).is_err());
+        vec.saturating_sub_at(0, 1);
+        assert_eq!(vec.get(0), -16);
+        vec.saturating_add_at(1, 40);
+        assert_eq!(vec.get(1), 15);
+        vec.saturating_add_at(2, 3);
+        vec.saturating_sub_at(5, usize::MAX);
+        assert_eq!((vec.get(2), vec.get(5)), (-11, -16));
+
+        // two's complement within the bits
+        let mut vec = BitsVec::new_signed(4);
+        vec.extend(vec![-1i8, 3, -8, 0]);
+        assert_eq!(vec.as_big_endian_value(4), 0xf380);
+        assert_eq!(&vec.to_bits()[..8], &[true, true, true, true, false, false, true, true]);
+        vec.truncate(3);
+        vec.widen_bits(7);
+        assert_eq!(vec.as_big_endian_value(3), 0b1111111_0000011_1111000);
+        let mut vec = BitsVec::new_signed(40);
+        vec.push(-(1i64 << 39));
+        vec.push((1i64 << 39) - 1);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![-(1i64 << 39), (1i64 << 39) - 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_signed_out_of_range() {
+        BitsVec::new_signed(4).push(8i8);
+    }
+
+    #[test]
+    fn test_widen_bits() {
+        let mut vec = BitsVec::from_iter(7, (0..300).map(|i| i % 128));
+        vec.widen_bits(7);
+        assert_eq!(vec.bits(), 7);
+        vec.widen_bits(13);
+        assert_eq!(vec.bits(), 13);
+        assert!(vec.iter().eq((0..300).map(|i| i % 128)));
+        vec.push(8191);
+        assert_eq!(vec.get(300), 8191);
+
+        let mut vec = BitsVec::new_signed(4);
+        vec.try_extend(-8..8i16).unwrap();
+        vec.widen_bits(9);
+        assert!(vec.iter().eq(-8..8));
+        vec.push(-256);
+        assert_eq!(vec.get(16), -256);
+
+        // elements crossing the words, and the new elements filling the last word
+        let mut vec = BitsVec::new_signed(5);
+        vec.try_extend((0..200).map(|i| (i % 32) as i8 - 16)).unwrap();
+        vec.widen_bits(63);
+        assert!(vec.iter().eq((0..200).map(|i| (i % 32) as i8 - 16)));
+        let mut vec = BitsVec::from_iter(4, (0..32).map(|i| i % 16));
+        vec.widen_bits(8);
+        assert_eq!(vec.as_big_endian_value(2), 0x0001);
+        vec.push(255u8);
+        assert!(vec.iter().eq((0..32).map(|i| i % 16).chain(Some(255))));
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let values = (0..1000).map(|i| (i * 37 % 4096) as u16).collect::<Vec<_>>();
+        let vec = BitsVec::from_slice(12, &values);
+        assert_eq!(vec.inner_len(), 1000 * 12 / 64 + 1);
+        assert_eq!(vec.to_vec(), values);
+        assert_eq!(Vec::from(vec), values);
+        assert_eq!(BitsVec::<u16>::new(12).to_vec(), Vec::<u16>::new());
+    }
+
+    #[test]
+    fn test_rle() {
+        let mut vec = BitsVec::new(3);
+        for i in 0..2000 {
+            vec.push((i / 100 % 8) as u8);
+        }
+
+        let (values, lengths) = vec.to_rle();
+        assert_eq!(values.len(), 20);
+        assert_eq!(lengths.iter().collect::<Vec<_>>(), vec![100; 20]);
+        assert_eq!(BitsVec::from_rle(&values, &lengths), vec);
+        assert!(values.inner_len() + lengths.inner_len() < vec.inner_len() / 10);
+
+        let mut signed = BitsVec::new_signed(4);
+        signed.extend(vec![-3i8, -3, 7, -8, -8, -8]);
+        let (values, lengths) = signed.to_rle();
+        assert_eq!(values.to_vec(), vec![-3, 7, -8]);
+        assert_eq!(BitsVec::from_rle(&values, &lengths), signed);
+
+        let (values, lengths) = BitsVec::<u8>::new(2).to_rle();
+        assert!(values.is_empty() && lengths.is_empty());
+        assert!(BitsVec::from_rle(&values, &lengths).is_empty());
+    }
+
+    #[test]
+    fn test_signed_and_unsigned_modes() {
+        let mut vec = BitsVec::new_signed(4);
+        vec.push(-3i8);
+        vec.extend(vec![0, 3]);
+        assert_eq!(vec.to_vec(), vec![-3, 0, 3]);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.pop(), Some(0));
+        assert_eq!(vec.pop(), Some(-3));
+
+        let mut vec = BitsVec::new(4);
+        assert!(vec.try_push(-3i8).is_err());
+        vec.push(15);
+        assert_eq!(vec.get(0), 15);
+        assert!(BitsVec::new_signed(4).try_push(15i8).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let vec = BitsVec::from_iter(13, (0..500).map(|i| i * 13 % 8192));
+        let json = serde_json::to_string(&vec).unwrap();
+        let decoded: BitsVec<u16> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, vec);
+        assert_eq!(decoded.to_vec(), vec.to_vec());
+
+        let mut signed = BitsVec::new_signed(13);
+        signed.push(-4096i16);
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(serde_json::from_str::<BitsVec<i16>>(&json).unwrap().get(0), -4096);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_invalid() {
+        let max = usize::MAX.count_ones() as usize;
+        let check = |vec: &BitsVec<u16>, field: &str, value: serde_json::Value| {
+            let mut json = serde_json::to_value(vec).unwrap();
+            json[field] = value;
+            serde_json::from_value::<BitsVec<u16>>(json).is_ok()
+        };
+
+        let vec = BitsVec::from_iter(13, (0..500).map(|i| i * 13 % 8192));
+        assert!(check(&vec, "bits", 13.into()));
+        assert!(!check(&vec, "bits", max.into()));
+        assert!(!check(&vec, "max_bits", 32.into()));
+        assert!(!check(&vec, "units", 600.into()));
+        assert!(!check(&vec, "inner", serde_json::to_value(&vec.as_words()[1..]).unwrap()));
+        assert!(!check(&vec, "leftover", 0.into()));
+        assert!(!check(&vec, "units", usize::MAX.into()));
+
+        // vectors ending at a word boundary (with and without the unused word at the end)
+        let mut vec = BitsVec::from_iter(8, (0..(2 * max / 8)).map(|i| i as u16));
+        assert!(check(&vec, "leftover", 0.into()));
+        assert!(!check(&vec, "leftover", max.into()));
+        vec.truncate(max / 8);
+        assert_eq!(vec.inner_len(), 2);
+        assert!(check(&vec, "leftover", max.into()));
+        assert!(!check(&vec, "leftover", 0.into()));
+        assert!(check(&BitsVec::new(5), "leftover", max.into()));
+        let mut json = serde_json::to_value(BitsVec::<u16>::new(5)).unwrap();
+        json["inner"] = serde_json::to_value(vec![usize::MAX]).unwrap();
+        let mut decoded = serde_json::from_value::<BitsVec<u16>>(json).unwrap();
+        decoded.push(3);
+        assert_eq!(decoded.to_vec(), vec![3]);
+        assert!(!check(&BitsVec::new(5), "inner", serde_json::Value::Array(vec![])));
+    }
+
+    #[test]
+    fn test_big_endian_value() {
+        let bases = b"GATTACACCGT".iter().map(|&b| match b {
+            b'A' => 0u8,
+            b'C' => 1,
+            b'G' => 2,
+            _ => 3,
+        }).collect::<Vec<_>>();
+        let vec = BitsVec::from_slice(2, &bases);
+        let manual = |window: &[u8]| window.iter().fold(0u128, |v, &d| v * 4 + d as u128);
+        assert_eq!(vec.as_big_endian_value(0), 0);
+        assert_eq!(vec.as_big_endian_value(4), 0b10_00_11_11);
+        assert_eq!(vec.as_big_endian_value(11), manual(&bases));
+
+        let mut value = vec.as_big_endian_value(5);
+        for i in 5..bases.len() {
+            value = vec.rolling_hash_step(value, 5, i);
+            assert_eq!(value, manual(&bases[i - 4..i + 1]));
+        }
+
+        let vec = BitsVec::from_iter(32, (0..5).map(|i| u32::MAX - i));
+        let value = vec.as_big_endian_value(4);
+        assert_eq!(value >> 96, u32::MAX as u128);
+        assert_eq!(vec.rolling_hash_step(value, 4, 4) as u32, u32::MAX - 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_big_endian_value_overflow() {
+        BitsVec::from_iter(32, 0..5u32).as_big_endian_value(5);
+    }
+
+    #[test]
+    fn test_ord() {
+        let a = BitsVec::from_slice(3, &[1u8, 2, 3]);
+        let b = BitsVec::from_slice(3, &[1u8, 2, 4]);
+        assert!(a < b);
+        assert!(BitsVec::from_slice(3, &[1u8, 2]) < a);
+        assert!(BitsVec::<u8>::new(3) < BitsVec::from_slice(3, &[0u8]));
+        assert!(BitsVec::from_slice(7, &[1u8, 2, 3]) < b);
+        assert!(BitsVec::from_slice(2, &[3u8, 3, 3]) > BitsVec::from_slice(7, &[3u8, 3, 1]));
+        assert_eq!(a.cmp(&a.clone()), cmp::Ordering::Equal);
+        assert!(a < BitsVec::from_slice(4, &[1u8, 2, 3]));
+
+        let mut signed = BitsVec::new_signed(4);
+        signed.extend(vec![-3i8, 2]);
+        let mut other = BitsVec::new_signed(4);
+        other.extend(vec![1i8]);
+        assert!(signed < other);
+
+        let mut sorted = vec![b.clone(), a.clone(), BitsVec::from_slice(3, &[0u8; 4])];
+        sorted.sort();
+        assert_eq!(sorted, vec![BitsVec::from_slice(3, &[0u8; 4]), a, b]);
+    }
+
+    #[test]
+    fn test_eq_ignores_stale_bits() {
+        let hash = |vec: &BitsVec<u8>| {
+            let mut hasher = DefaultHasher::new();
+            vec.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // 64 values of 5 bits fill the words exactly (on both 32-bit and 64-bit platforms)
+        let values = (0..64).map(|i| (i * 7 % 32) as u8).collect::<Vec<_>>();
+        let expected = BitsVec::from_iter(5, values.iter().cloned());
+        let mut vec = BitsVec::new(5);
+        vec.extend(values.iter().cloned());
+        vec.push(31);
+        assert_eq!(vec.pop(), Some(31));
+        assert!(vec.inner_len() != expected.inner_len());
+        assert_eq!(vec, expected);
+        assert_eq!(hash(&vec), hash(&expected));
+
+        let mut long = BitsVec::from_iter(5, (0..100).map(|_| 31u8));
+        long.truncate(0);
+        long.extend(values.iter().cloned());
+        assert_eq!(long, expected);
+        assert_eq!(hash(&long), hash(&expected));
+        long.set(4, 8);
+        assert!(long != expected);
+    }
+
+    #[test]
+    fn test_delta_builder() {
+        let values = (0..1000).map(|i| i * i / 7 + 40).collect::<Vec<usize>>();
+        let mut builder = DeltaBuilder::new(9);
+        assert!(builder.is_empty());
+        for &v in &values {
+            builder.push_monotonic(v).unwrap();
+        }
+
+        assert_eq!(builder.push_monotonic(10), Err(MonotonicError { value: 10, last: values[999] }));
+        assert_eq!((builder.len(), builder.last()), (1000, Some(values[999])));
+        builder.push_monotonic(values[999]).unwrap();
+        let vec = builder.build();
+        let expected = values.iter().chain(Some(&values[999])).map(|&v| v as u64).collect::<Vec<_>>();
+        assert_eq!(vec.to_sorted_values(), expected);
+        assert_eq!(vec, BitsVec::from_iter(9, BitsVec::from_sorted_deltas(&expected).iter()));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_memory_bytes() {
+        let mut vec = BitsVec::with_elements(2, 1000, 0u8);
+        assert_eq!(vec.bits_per_element(), 2);
+        assert_eq!(vec.inner_len(), 32);
+        assert_eq!(mem::size_of::<BitsVec<u8>>(), 64);
+        assert_eq!(vec.memory_bytes(), 33 * 8 + 64);
+        vec.shrink_to_fit();
+        assert_eq!(vec.memory_bytes(), 32 * 8 + 64);
+        vec.reserve(1000);
+        assert!(vec.memory_bytes() >= 64 * 8 + 64);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_with_elements() {
+        for &bits in &[1, 2, 3, 5, 8, 13, 22, 31, 63] {
+            let value = (1usize << bits) - 2;
+            for &length in &[1, 7, 64, 1000, 100_003] {
+                let vec = BitsVec::par_with_elements(bits, length, value);
+                assert_eq!(vec, BitsVec::with_elements(bits, length, value));
+                assert_eq!(vec.len(), length);
+                assert_eq!(vec.get(length - 1), value);
+            }
+        }
+
+        let mut vec = BitsVec::par_with_elements(5, 64, 3u8);
+        vec.push(4);
+        assert_eq!(vec.get(63), 3);
+        assert_eq!(vec.get(64), 4);
+        assert!(BitsVec::par_with_elements(5, 0, 3u8).is_empty());
+        let vec = BitsVec::par_with_elements(0, 1000, 0u8);
+        assert_eq!((vec.len(), vec.as_words()), (1000, &[0][..]));
+    }
+
+    #[test]
+    fn test_shift() {
+        let values = (0..100).map(|i| (i % 13) as u8).collect::<Vec<_>>();
+        let mut vec = BitsVec::from_slice(4, &values);
+        vec.shift_left(2, 15);
+        assert_eq!(vec.to_vec()[..98], values[2..]);
+        assert_eq!(vec.to_vec()[98..], [15, 15]);
+
+        let mut vec = BitsVec::from_slice(4, &values);
+        vec.shift_right(33, 14);
+        assert_eq!(vec.to_vec()[..33], [14; 33]);
+        assert_eq!(vec.to_vec()[33..], values[..67]);
+
+        vec.shift_left(0, 0);
+        vec.shift_right(0, 0);
+        assert_eq!(vec.to_vec()[33..], values[..67]);
+        vec.shift_left(1000, 7);
+        assert_eq!(vec, BitsVec::with_elements(4, 100, 7));
+        vec.shift_right(100, 9);
+        assert_eq!(vec, BitsVec::with_elements(4, 100, 9));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shift_out_of_range() {
+        BitsVec::from_slice(4, &[1u8, 2, 3]).shift_left(1, 16);
+    }
+
+    #[test]
+    fn test_raw_parts() {
+        let vec = BitsVec::from_iter(13, (0..777).map(|i| (i * 31 % 8192) as u16));
+        let (inner, bits, units) = vec.clone().into_raw_parts();
+        assert_eq!((inner.len(), bits, units), (vec.inner_len(), 13, 777));
+        let ptr = inner.as_ptr();
+        let mut decoded = unsafe { BitsVec::<u16>::from_raw_parts(inner, bits, units) };
+        assert!(!decoded.is_signed());
+        assert_eq!(decoded.as_words().as_ptr(), ptr);
+        assert_eq!(decoded, vec);
+        decoded.push(8191);
+        assert_eq!(decoded.get(777), 8191);
+
+        // stale words (and bits) at the end are cleared
+        let mut words = vec.as_words().to_vec();
+        words.extend(vec![usize::MAX; 3]);
+        let decoded = unsafe { BitsVec::<u16>::from_raw_parts(words, 13, 700) };
+        assert_eq!(decoded.to_vec(), vec.to_vec()[..700]);
+        assert_eq!(decoded.as_words(), BitsVec::from_iter(13, vec.iter().take(700)).as_words());
+        assert!(unsafe { BitsVec::<u16>::from_raw_parts(vec![], 13, 0) }.is_empty());
+
+        let mut vec = BitsVec::new_signed(6);
+        (-32..32).for_each(|i| vec.push(i as i8));
+        assert!(vec.is_signed());
+        let (inner, bits, units) = vec.clone().into_raw_parts();
+        let decoded = unsafe { BitsVec::<i8>::from_raw_parts_signed(inner, bits, units) };
+        assert!(decoded.is_signed());
+        assert_eq!(decoded, vec);
+        assert!(decoded.iter().eq(-32..32));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_raw_parts_short_words() {
+        unsafe { BitsVec::<u16>::from_raw_parts(vec![0; 2], 13, 10) };
+    }
+
+    #[test]
+    fn test_as_raw_slice() {
+        let vec = BitsVec::from_iter(7, (0..500).map(|i| (i % 128) as u8));
+        assert_eq!(vec.as_raw_slice().len(), vec.inner_len());
+        assert_eq!(vec.word_count(), vec.inner_len());
+        let bytes = vec.as_raw_slice().iter().flat_map(|w| w.to_ne_bytes().to_vec()).collect::<Vec<_>>();
+        assert_eq!(bytes.len(), vec.word_count() * mem::size_of::<usize>());
+        let words = bytes.chunks(mem::size_of::<usize>()).map(|c| {
+            let mut word = [0; mem::size_of::<usize>()];
+            word.copy_from_slice(c);
+            usize::from_ne_bytes(word)
+        }).collect::<Vec<_>>();
+        assert_eq!(unsafe { BitsVec::<u8>::from_raw_parts(words, 7, 500) }, vec);
+    }
+
+    #[test]
+    fn test_display() {
+        let vec = BitsVec::from_iter(14, 0..10_000u16);
+        assert_eq!(format!("{}", vec), "[0, 1, 2, 3, 4, ..., 9995, 9996, 9997, 9998, 9999]");
+        assert_eq!(format!("{:.1}", vec), "[0, ..., 9999]");
+        assert_eq!(format!("{:.0}", vec), "[...]");
+        assert_eq!(format!("{:.5000}", vec).len(), format!("{:?}", vec).len());
+        assert_eq!(format!("{}", BitsVec::from_iter(14, 0..10u16)), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+        assert_eq!(format!("{}", BitsVec::<u16>::new(14)), "[]");
+    }
+
+    #[test]
+    fn test_chunks() {
+        let vec = BitsVec::from_iter(3, (0..10u8).map(|i| i % 8));
+        let mut chunks = vec.chunks(4);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.next(), Some(vec![0, 1, 2, 3]));
+        assert_eq!(chunks.size_hint(), (2, Some(2)));
+        assert_eq!(chunks.collect::<Vec<_>>(), vec![vec![4, 5, 6, 7], vec![0, 1]]);
+        assert_eq!(vec.chunks(4).map(|c| c.len()).collect::<Vec<_>>(), vec![4, 4, 2]);
+        assert_eq!(vec.chunks(5).len(), 2);
+        assert_eq!(vec.chunks(100).collect::<Vec<_>>(), vec![vec.to_vec()]);
+        assert_eq!(BitsVec::<u8>::new(3).chunks(4).len(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_chunks_zero_size() {
+        BitsVec::<u8>::new(3).chunks(0);
+    }
+
+    #[test]
+    fn test_windows() {
+        let bases = b"GATTACACCGTAG".iter().map(|&b| match b {
+            b'A' => 0u8,
+            b'C' => 1,
+            b'G' => 2,
+            _ => 3,
+        }).collect::<Vec<_>>();
+        let vec = BitsVec::from_slice(2, &bases);
+        let mut windows = vec.windows(3);
+        assert_eq!(windows.len(), bases.len() - 2);
+        assert_eq!(windows.next(), Some(bases[..3].to_vec()));
+        assert_eq!(windows.len(), bases.len() - 3);
+        assert_eq!(windows.collect::<Vec<_>>(), bases.windows(3).skip(1).map(|w| w.to_vec()).collect::<Vec<_>>());
+        assert_eq!(vec.windows(13).collect::<Vec<_>>(), vec![bases.clone()]);
+        assert_eq!(vec.windows(14).len(), 0);
+        assert_eq!(vec.windows(14).next(), None);
+        assert_eq!(BitsVec::<u8>::new(2).windows(1).len(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_windows_zero_size() {
+        BitsVec::<u8>::new(2).windows(0);
+    }
+
+    #[test]
+    fn test_to_bits() {
+        let vec = BitsVec::from_iter(3, (0..100).map(|i| (i * 5 % 8) as u8));
+        let bits = vec.to_bits();
+        assert_eq!(bits.len(), 300);
+        assert_eq!(bits[..6], [false, false, false, true, false, true]);
+        assert_eq!(bits.iter().filter(|&&b| b).count(),
+                   vec.iter().map(|v| v.count_ones() as usize).sum::<usize>());
+        assert_eq!(BitsVec::from_bits(3, &bits), vec);
+        assert!(BitsVec::<u8>::from_bits(3, &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_bits_partial_element() {
+        BitsVec::<u8>::from_bits(3, &[true, false, true, true]);
+    }
+
+    #[test]
+    fn test_binary_search() {
+        let vec = BitsVec::from_iter(12, (0..2000u16).map(|i| i * 2));
+        for i in 0..2000 {
+            assert_eq!(vec.binary_search(&(i * 2)), Ok(i as usize));
+            assert_eq!(vec.binary_search(&(i * 2 + 1)), Err(i as usize + 1));
+        }
+
+        assert_eq!(vec.binary_search(&4095), Err(2000));
+        assert_eq!(BitsVec::<u16>::new(12).binary_search(&3), Err(0));
+
+        let mut signed = BitsVec::new_signed(6);
+        signed.extend(vec![-32i8, -5, 0, 3, 31]);
+        assert_eq!(signed.binary_search(&-5), Ok(1));
+        assert_eq!(signed.binary_search(&-6), Err(1));
+        assert_eq!(signed.binary_search(&-32), Ok(0));
+    }
+
+    #[test]
+    fn test_reverse() {
+        let original = BitsVec::from_iter(5, 1..=20u8);
+        let mut vec = original.clone();
+        vec.reverse();
+        assert_eq!(vec.to_vec(), (1..=20).rev().collect::<Vec<_>>());
+        vec.reverse();
+        assert_eq!(vec, original);
+        vec.push(31);
+        assert_eq!(vec.get(20), 31);
+        assert_eq!(vec.len(), 21);
+        vec.reverse();
+        assert_eq!(vec.to_vec()[..2], [31, 20]);
+
+        let mut empty = BitsVec::<u8>::new(5);
+        empty.reverse();
+        assert!(empty.is_empty());
     }
 }
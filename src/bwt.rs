@@ -1,6 +1,72 @@
-use sa::{insert, suffix_array};
+use cache::QueryCache;
+use dna::{complement, iupac_bases, reverse_complement};
+use sa::{insert, suffix_array, suffix_array_cancellable, suffix_array_parallel};
 
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::{BTreeSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::iter::FromIterator;
+use std::mem;
 use std::ops::Index;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Number of iterations after which the cancellation flag is checked while building the index
+// (should be a power of two)
+const CANCEL_CHECK_INTERVAL: usize = 1 << 16;
+
+// The one-letter codes of the 20 standard amino acids
+const AMINO_ACIDS: &[u8] = b"ACDEFGHIKLMNPQRSTVWY";
+
+// Interval of the occurrence checkpoints for the protein indexes
+const PROTEIN_OCC_INTERVAL: usize = 64;
+
+// Magic bytes (and the version) at the start of the compact format
+const COMPACT_MAGIC: &[u8] = b"FMIC\x01";
+
+/// The error returned when the input has the null byte, which is reserved for
+/// marking the end of the text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NullByteError {
+    /// The position of the first null byte in the input
+    pub position: usize,
+}
+
+impl fmt::Display for NullByteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "input has the (reserved) null byte at position {}", self.position)
+    }
+}
+
+impl Error for NullByteError {}
+
+/// The positions to which the matches are anchored in `FMIndex::search_anchored`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Anchor {
+    /// Matches at the start of the text
+    Start,
+    /// Matches at the end of the text
+    End,
+    /// Matches spanning the entire text
+    Both,
+}
+
+/// A summary of an FM-index (see `FMIndex::stats`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IndexStats {
+    /// The length of the original text
+    pub text_len: usize,
+    /// The number of distinct bytes in the original text
+    pub alphabet_size: usize,
+    /// The number of runs (of the same byte) in the BWT
+    pub bwt_runs: usize,
+    /// The average length of the runs in the BWT
+    pub mean_run_len: f64,
+    /// The (approximate) number of bytes consumed by the index
+    pub memory_bytes: usize,
+}
 
 /// Generate the [Burrows-Wheeler Transform](https://en.wikipedia.org/wiki/Burrows%E2%80%93Wheeler_transform)
 /// of the given input.
@@ -13,20 +79,59 @@ use std::ops::Index;
 /// ```
 /// The output can then be used for compression or FM-index'ing.
 pub fn bwt(input: &[u8]) -> Vec<u8> {
-    suffix_array(input).into_iter().map(|i| {
+    bwt_from_sa(input, suffix_array(input))
+}
+
+fn bwt_from_sa(input: &[u8], sa: Vec<u32>) -> Vec<u8> {
+    sa.into_iter().map(|i| {
         // BWT[i] = S[SA[i] - 1]
         if i == 0 { 0 } else { input[(i - 1) as usize] }
     }).collect()
 }
 
+// Build a (one-to-one) table mapping the bytes to the given symbols, assigning
+// the unused symbols to the unmapped bytes in ascending order.
+fn remap_table(mapping: &[(u8, u8)]) -> Vec<u8> {
+    let (mut table, mut used) = (vec![None; 256], vec![false; 256]);
+    for &(byte, symbol) in mapping {
+        assert!(byte != 0 && symbol != 0, "[remap] null byte is reserved for the end of text");
+        assert!(table[byte as usize].is_none() && !used[symbol as usize],
+                "[remap] mapping should be one-to-one (found {} -> {})", byte, symbol);
+        table[byte as usize] = Some(symbol);
+        used[symbol as usize] = true;
+    }
+
+    let mut unused = (0..256).filter(|&s| !used[s]).map(|s| s as u8);
+    table.into_iter().map(|s| s.unwrap_or_else(|| unused.next().unwrap())).collect()
+}
+
+// Get the length of the longest common prefix of the suffixes at the given positions
+// (the longest common extension).
+fn lce(text: &[u8], i: usize, j: usize) -> usize {
+    assert!(i <= text.len() && j <= text.len(),
+            "[lce] positions out of range ({}, {} for length {})", i, j, text.len());
+    text[i..].iter().zip(&text[j..]).take_while(|&(a, b)| a == b).count()
+}
+
+// Hash a k-mer (FNV-1a, followed by the finalizer of MurmurHash3 for mixing the bits),
+// so that the minimizers aren't biased towards the lexicographically smaller k-mers.
+fn kmer_hash(kmer: &[u8]) -> u64 {
+    let mut hash = kmer.iter().fold(0xcbf29ce484222325, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
 // Takes a frequency map of bytes and generates the index of first occurrence
 // of each byte.
-fn generate_occurrence_index(map: &mut Vec<u32>) {
+fn generate_occurrence_index(map: &mut [u32]) {
     let mut idx = 0;
 
-    for i in 0..map.len() {
-        let c = map[i];
-        map[i] = idx;
+    for value in map.iter_mut() {
+        let c = *value;
+        *value = idx;
         idx += c;
     }
 }
@@ -69,6 +174,33 @@ pub fn ibwt(input: &[u8]) -> Vec<u8> {
     output
 }
 
+// Write a (little-endian) length.
+fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    writer.write_all(&(len as u64).to_le_bytes())
+}
+
+// Read a (little-endian) length, which shouldn't exceed the given limit (to guard against
+// the allocations of corrupted lengths).
+fn read_len<R: Read>(reader: &mut R, limit: usize) -> io::Result<usize> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    match u64::from_le_bytes(bytes) {
+        len if len <= limit as u64 => Ok(len as usize),
+        len => Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid length {} (> {})", len, limit))),
+    }
+}
+
+// Read the given number of bytes.
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of stream"))
+    }
+
+    Ok(bytes)
+}
+
 /// [Ferragina-Manzini index](https://en.wikipedia.org/wiki/FM-index)
 /// (or Full-text index in Minute space) for finding occurrences of substrings
 /// in O(1) time.
@@ -102,6 +234,16 @@ pub struct FMIndex {
     occ_map: Vec<u32>,
     /// LF-mapping for backward search
     lf_vec: Vec<u32>,
+    /// original text (if it's been retained for extraction)
+    text: Option<Vec<u8>>,
+    /// starting positions of the documents in the text
+    doc_starts: Vec<usize>,
+    /// mapping of the bytes to the symbols held by the index (if it's been remapped)
+    remap: Option<Vec<u8>>,
+    /// interval of the occurrence checkpoints (zero if there aren't any)
+    occ_interval: usize,
+    /// frequencies of all the characters before every checkpoint
+    checkpoints: Vec<u32>,
 }
 
 impl FMIndex {
@@ -111,14 +253,466 @@ impl FMIndex {
         FMIndex::new_from_bwt(bwt(data))
     }
 
+    /// Generate an FM-index for the input data, with the character frequencies stored at every
+    /// "N" positions of the BWT (checkpoints).
+    ///
+    /// By default, `nearest` scans backwards (from the given position) for the nearest occurrence
+    /// of the character, which can take O(n) time for the rare characters. With checkpoints, it
+    /// only counts the characters since the last checkpoint, which takes O(interval) time. The
+    /// checkpoints need `4 * (largest_byte + 1) * n / interval` bytes, but they replace the forward
+    /// frequencies (`4 * n` bytes). So, a smaller interval means faster queries, but more memory
+    /// (see `memory_bytes`). Note that this panics when the interval is zero.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let text = b"GCGTGCCCAGGGCACTGCCGCTGCAGGCGTAGGCATCGCATCACACGCGT" as &[u8];
+    /// let index = FMIndex::with_occ_interval(text, 16);
+    /// assert_eq!(index.search("GCGT"), FMIndex::new(text).search("GCGT"));
+    /// ```
+    pub fn with_occ_interval(data: &[u8], interval: usize) -> FMIndex {
+        let mut index = FMIndex::new(data);
+        index.build_checkpoints(interval);
+        index
+    }
+
+    // Build the occurrence checkpoints (at the given interval), replacing the forward frequencies.
+    fn build_checkpoints(&mut self, interval: usize) {
+        assert!(interval > 0, "[occ_interval] interval should be greater than zero");
+        let alphabet = self.occ_map.len();
+        let mut counts = vec![0u32; alphabet];
+        self.checkpoints = Vec::with_capacity((self.data.len() / interval + 1) * alphabet);
+        for (i, &b) in self.data.iter().enumerate() {
+            if i % interval == 0 {
+                self.checkpoints.extend_from_slice(&counts);
+            }
+
+            counts[b as usize] += 1;
+        }
+
+        if self.data.len().is_multiple_of(interval) {
+            self.checkpoints.extend_from_slice(&counts);
+        }
+
+        self.occ_interval = interval;
+        self.cache = Vec::new();
+    }
+
+    /// Get the (approximate) number of bytes consumed by the index on the heap.
+    pub fn memory_bytes(&self) -> usize {
+        let words = self.cache.len() + self.occ_map.len() + self.lf_vec.len() + self.checkpoints.len();
+        self.data.len() + 4 * words + self.text.as_ref().map_or(0, |t| t.len()) +
+            mem::size_of::<usize>() * self.doc_starts.len() + self.remap.as_ref().map_or(0, |r| r.len())
+    }
+
+    /// Get a summary of the index.
+    ///
+    /// The number of runs in the BWT is a measure of the repetitiveness of the text - the BWT of
+    /// a repetitive text (like a collection of genomes) has long runs, which is where the run-length
+    /// compressed indexes would save a lot of memory.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let stats = FMIndex::new(b"Hello, Hello, Hello" as &[u8]).stats();
+    /// assert_eq!((stats.text_len, stats.alphabet_size), (19, 6));
+    /// ```
+    pub fn stats(&self) -> IndexStats {
+        let runs = self.data.windows(2).filter(|w| w[0] != w[1]).count() + 1;
+        IndexStats {
+            text_len: self.data.len() - 1,
+            alphabet_size: self.symbol_order().len(),
+            bwt_runs: runs,
+            mean_run_len: self.data.len() as f64 / runs as f64,
+            memory_bytes: self.memory_bytes(),
+        }
+    }
+
+    /// Write the index in a compact format, which can be read using `load_compact`.
+    ///
+    /// The LF-mapping and the frequency tables (each of which takes four times the size of the
+    /// BWT) are derived from the BWT, so only the BWT is written (along with the retained text,
+    /// the documents, the mapping of the bytes and the checkpoint interval, if any). This
+    /// shrinks the persisted index to about a ninth of its size in memory, but the tables
+    /// are rebuilt while loading, which takes linear time (the suffix array isn't rebuilt).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"Hello, Hello, Hello" as &[u8]);
+    /// let mut bytes = Vec::new();
+    /// index.save_compact(&mut bytes).unwrap();
+    /// assert_eq!(FMIndex::load_compact(&bytes[..]).unwrap().search("llo"), vec![16, 9, 2]);
+    /// ```
+    pub fn save_compact<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(COMPACT_MAGIC)?;
+        write_len(&mut writer, self.data.len())?;
+        writer.write_all(&self.data)?;
+        write_len(&mut writer, self.occ_interval)?;
+        write_len(&mut writer, self.doc_starts.len())?;
+        for &start in &self.doc_starts {
+            write_len(&mut writer, start)?;
+        }
+
+        for bytes in &[&self.text, &self.remap] {
+            match **bytes {
+                Some(ref bytes) => {
+                    writer.write_all(&[1])?;
+                    write_len(&mut writer, bytes.len())?;
+                    writer.write_all(bytes)?;
+                },
+                None => writer.write_all(&[0])?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read an index written by `save_compact`, returning an error (of kind `InvalidData`) if
+    /// the stream isn't in the compact format, or if its parts don't agree with the BWT (the
+    /// documents should start at zero and be in ascending order within the text, the retained
+    /// text should be as long as the BWT without the null byte, and the mapping should have
+    /// a symbol for every byte).
+    pub fn load_compact<R: Read>(mut reader: R) -> io::Result<FMIndex> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_owned());
+        if read_bytes(&mut reader, COMPACT_MAGIC.len())? != COMPACT_MAGIC {
+            return Err(invalid("not an index in the compact format"))
+        }
+
+        let length = read_len(&mut reader, u32::MAX as usize)?;
+        let data = read_bytes(&mut reader, length)?;
+        if !data.contains(&0) {
+            return Err(invalid("BWT should have the null byte"))
+        }
+
+        let occ_interval = read_len(&mut reader, length)?;
+        let docs = read_len(&mut reader, length)?;
+        let doc_starts = (0..docs).map(|_| read_len(&mut reader, length)).collect::<io::Result<Vec<_>>>()?;
+        let mut optional = Vec::with_capacity(2);
+        for &limit in &[length, 256] {
+            let mut flag = [0];
+            reader.read_exact(&mut flag)?;
+            optional.push(match flag[0] {
+                0 => None,
+                _ => {
+                    let len = read_len(&mut reader, limit)?;
+                    Some(read_bytes(&mut reader, len)?)
+                },
+            });
+        }
+
+        if doc_starts.first() != Some(&0) || doc_starts.windows(2).any(|w| w[0] > w[1]) ||
+           doc_starts.last().is_some_and(|&start| start > length - 1) {
+            return Err(invalid("documents should start at zero and be in ascending order"))
+        }
+
+        if optional[0].as_ref().is_some_and(|text| text.len() != length - 1) {
+            return Err(invalid("retained text should be as long as the BWT (without the null byte)"))
+        }
+
+        if optional[1].as_ref().is_some_and(|remap| remap.len() != 256) {
+            return Err(invalid("mapping should have a symbol for every byte"))
+        }
+
+        let mut index = FMIndex::new_from_bwt(data);
+        index.doc_starts = doc_starts;
+        index.remap = optional.pop().unwrap();
+        index.text = optional.pop().unwrap();
+        if occ_interval > 0 {
+            index.build_checkpoints(occ_interval);
+        }
+
+        Ok(index)
+    }
+
+    /// Generate an FM-index for the input data, or an error if the data has a null byte.
+    ///
+    /// The null byte marks the end of the text in the BWT, and so the index is silently wrong
+    /// for the inputs having a null byte (`new` doesn't check this).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// assert!(FMIndex::try_new(b"ACGT" as &[u8]).is_ok());
+    /// assert_eq!(FMIndex::try_new(b"AC\0GT" as &[u8]).unwrap_err().position, 2);
+    /// ```
+    pub fn try_new(data: &[u8]) -> Result<FMIndex, NullByteError> {
+        match data.iter().position(|&b| b == 0) {
+            Some(position) => Err(NullByteError { position }),
+            None => Ok(FMIndex::new(data)),
+        }
+    }
+
+    /// Generate an FM-index for the input data, using multiple threads for building
+    /// the suffix array.
+    ///
+    /// The data is split into a range for each thread, and the suffixes starting in each range
+    /// are sorted in parallel (by induced sorting, along with a bit per byte that compares the
+    /// following suffix with the one after the range). The sorted ranges are then merged in pairs
+    /// (by backward searching the left range over the right one), and the pairs in each round
+    /// are merged in parallel. The resulting suffix array (and hence, the index) is identical
+    /// to the one from `new`. Note that the last merge ranks half of the suffixes with a single
+    /// thread, so this needs a few cores to be faster than `new`.
+    pub fn new_parallel(data: &[u8], threads: usize) -> FMIndex {
+        FMIndex::new_from_bwt(bwt_from_sa(data, suffix_array_parallel(data, threads)))
+    }
+
+    /// Generate an FM-index for the input data, and retain a copy of the data.
+    ///
+    /// This consumes additional memory (the size of the input), but `extract` can then
+    /// slice into the text directly instead of walking through the LF-mapping.
+    pub fn new_with_text(data: &[u8]) -> FMIndex {
+        let mut index = FMIndex::new(data);
+        index.text = Some(data.to_vec());
+        index
+    }
+
+    /// Generate an FM-index for multiple documents (sequences).
+    ///
+    /// The documents are concatenated and indexed as a single text, and so the positions from
+    /// `search` (and friends) are positions in the concatenated text. The boundaries of the
+    /// documents are stored, and `locate_in_documents` can be used to get the positions within
+    /// the documents instead.
+    pub fn new_multi(docs: &[&[u8]]) -> FMIndex {
+        let mut doc_starts = Vec::with_capacity(docs.len());
+        let mut data = Vec::with_capacity(docs.iter().map(|d| d.len()).sum());
+        for doc in docs {
+            doc_starts.push(data.len());
+            data.extend_from_slice(doc);
+        }
+
+        let mut index = FMIndex::new(&data);
+        index.doc_starts = doc_starts;
+        index
+    }
+
+    /// Generate an FM-index for the input data, after mapping the bytes to the given symbols
+    /// (ranks), so that the index orders the bytes in the caller's order.
+    ///
+    /// The `(byte, symbol)` pairs should be one-to-one, and the unmapped bytes are assigned the
+    /// unused symbols in ascending order. The mapping is stored, and the queries (and the
+    /// extracted bytes) are translated, so the index behaves as if it's been generated using `new`,
+    /// except that the BWT ranges (and `bwt`) follow the mapped order. Note that this panics
+    /// if the mapping isn't one-to-one, or if it includes the null byte (which marks the end of the text).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let mapping = [(b'T', 1), (b'G', 2), (b'C', 3), (b'A', 4)];
+    /// let index = FMIndex::new_with_mapping(b"GCGTAGGCATCGCAT" as &[u8], &mapping);
+    /// assert_eq!(index.symbol_order(), b"TGCA");
+    /// assert_eq!(index.search("CAT"), vec![12, 7]);
+    /// ```
+    pub fn new_with_mapping(data: &[u8], mapping: &[(u8, u8)]) -> FMIndex {
+        let table = remap_table(mapping);
+        let mapped = data.iter().map(|&b| table[b as usize]).collect::<Vec<_>>();
+        let mut index = FMIndex::new(&mapped);
+        index.remap = Some(table);
+        index
+    }
+
+    /// Generate an FM-index for a protein sequence, made of the (uppercase) one-letter codes
+    /// of the 20 standard amino acids.
+    ///
+    /// The codes are mapped to the symbols `1..=20` (see `new_with_mapping`), so that the
+    /// frequency tables only span the amino acids, and the occurrences are counted using
+    /// checkpoints (see `with_occ_interval`), whose size depends on that alphabet. This keeps
+    /// `nearest` bounded (unlike the backward scan, which could go through long stretches
+    /// of other residues), while using less memory than `new`. Note that this panics if the
+    /// sequence has any other byte.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new_protein(b"MKTAYIAKQRQISFVKSHFSRQ" as &[u8]);
+    /// assert_eq!(index.search("KQR"), vec![7]);
+    /// assert_eq!(index.count("SR"), 1);
+    /// ```
+    pub fn new_protein(data: &[u8]) -> FMIndex {
+        if let Some(i) = data.iter().position(|b| !AMINO_ACIDS.contains(b)) {
+            panic!("[new_protein] unknown amino acid code {:?} at position {}", data[i] as char, i);
+        }
+
+        let mapping = AMINO_ACIDS.iter().enumerate()
+                                 .map(|(i, &b)| (b, i as u8 + 1))
+                                 .collect::<Vec<_>>();
+        let mut index = FMIndex::new_with_mapping(data, &mapping);
+        index.build_checkpoints(PROTEIN_OCC_INTERVAL);
+        index
+    }
+
+    /// Generate an FM-index for the input data, ordering the bytes by their (descending)
+    /// frequencies instead of their values.
+    ///
+    /// This is a `new_with_mapping` with the most frequent byte as the first symbol (and so on).
+    /// The results are the same as the ones from `new` (except for the BWT ranges), but `nearest`
+    /// goes through shorter runs of the frequent symbols while scanning backwards, which makes
+    /// the (common) queries involving them a little faster.
+    pub fn new_frequency_ordered(data: &[u8]) -> FMIndex {
+        let mut counts = vec![0usize; 256];
+        for &b in data {
+            counts[b as usize] += 1;
+        }
+
+        let mut bytes = (1..256).filter(|&b| counts[b] > 0).collect::<Vec<_>>();
+        bytes.sort_by(|&a, &b| counts[b].cmp(&counts[a]).then(a.cmp(&b)));
+        let mapping = bytes.into_iter().enumerate()
+                           .map(|(i, b)| (b as u8, i as u8 + 1))
+                           .collect::<Vec<_>>();
+        FMIndex::new_with_mapping(data, &mapping)
+    }
+
+    /// Merge two indexes into an index for the concatenation of their texts (the text of `a`
+    /// followed by that of `b`).
+    ///
+    /// The documents of both indexes are retained (so the documents of `b` follow those of `a`
+    /// in `locate_in_documents`), and so is the text, if it's been retained by both indexes.
+    /// The mapping of `a` (see `new_with_mapping`) is retained, and if `b` has been generated
+    /// with a different mapping, then it's rebuilt with the mapping of `a`.
+    ///
+    /// The BWTs are merged by interleaving the suffixes. The suffixes of `b` remain in the same
+    /// order, and the suffixes of `a` (which are now followed by the text of `b`) are ranked
+    /// among them by backward searching the text of `a` over `b`, and sorted (among themselves)
+    /// by their ranks. So, merging only sorts the suffixes of `a`, rather than the whole text
+    /// (the tables of the index are still rebuilt, which takes linear time).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let a = FMIndex::new(b"GCGTGCCCAG" as &[u8]);
+    /// let b = FMIndex::new(b"GGCACTGCGT" as &[u8]);
+    /// let index = FMIndex::merge(a, b);
+    /// assert_eq!(index.search("GCGT"), vec![16, 0]);
+    /// assert_eq!(index.count("AGG"), 1);      // across the texts
+    /// ```
+    pub fn merge(a: FMIndex, b: FMIndex) -> FMIndex {
+        let b = if a.remap == b.remap {
+            b
+        } else {
+            let data = b.text();
+            let mut index = match a.remap {
+                Some(ref table) => FMIndex::new(&data.iter().map(|&b| table[b as usize]).collect::<Vec<_>>()),
+                None => FMIndex::new(&data),
+            };
+
+            index.text = b.text.clone();
+            index.doc_starts = b.doc_starts.clone();
+            index
+        };
+
+        let symbols = match (&a.text, &a.remap) {
+            (Some(text), Some(table)) => text.iter().map(|&b| table[b as usize]).collect(),
+            (Some(text), None) => text.clone(),
+            _ => ibwt(&a.data),
+        };
+
+        // the number of suffixes (of `b`) smaller than each suffix of `a` (the last one being
+        // the entire text of `b`, which is at the row of the null byte)
+        let mut counts = vec![0usize; 257];
+        for &ch in &b.data {
+            counts[ch as usize + 1] += 1;
+        }
+
+        for i in 1..counts.len() {
+            counts[i] += counts[i - 1];
+        }
+
+        let mut ranks = vec![0; symbols.len() + 1];
+        ranks[symbols.len()] = b.data.iter().position(|&ch| ch == 0).unwrap();
+        for (i, &ch) in symbols.iter().enumerate().rev() {
+            let next = ranks[i + 1];
+            ranks[i] = if counts[ch as usize + 1] > counts[ch as usize] { b.nearest(next, ch) }
+                       else { counts[ch as usize] };
+        }
+
+        // The suffixes of `a` sharing a rank are ordered by their bytes, and then by the suffixes
+        // following them. So, sorting the suffixes of the ranks (interleaved with the bytes, where
+        // the text of `b` is in between the suffixes of `a` at its row) gives their order.
+        let keys = (0..ranks.len()).map(|i| {
+            let byte = symbols.get(i).map_or(0, |&b| b as usize);
+            let rank = if i < symbols.len() { 2 * ranks[i] } else { 2 * ranks[i] + 1 };
+            (rank << 8) | byte
+        }).collect::<Vec<_>>();
+
+        let mut distinct = keys.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let keys = keys.into_iter().map(|k| distinct.binary_search(&k).unwrap() as u32).collect::<Vec<_>>();
+        let order = suffix_array(&keys).into_iter().map(|i| i as usize).filter(|&i| i < symbols.len());
+
+        let last = symbols.last().cloned().unwrap_or(0);
+        let mut data = Vec::with_capacity(a.data.len() + b.data.len() - 1);
+        let mut order = order.peekable();
+        for (row, &ch) in b.data.iter().enumerate() {
+            while let Some(i) = order.next_if(|&i| ranks[i] == row) {
+                data.push(if i == 0 { 0 } else { symbols[i - 1] });
+            }
+
+            data.push(if ch == 0 { last } else { ch });
+        }
+
+        data.extend(order.map(|i| if i == 0 { 0 } else { symbols[i - 1] }));
+        let mut index = FMIndex::new_from_bwt(data);
+        index.remap = a.remap.clone();
+        if let (Some(first), Some(second)) = (&a.text, &b.text) {
+            let mut text = first.clone();
+            text.extend_from_slice(second);
+            index.text = Some(text);
+        }
+
+        let offset = a.data.len() - 1;
+        index.doc_starts = a.doc_starts.clone();
+        index.doc_starts.extend(b.doc_starts.iter().map(|&start| start + offset));
+        if a.occ_interval > 0 {
+            index.build_checkpoints(a.occ_interval);
+        }
+
+        index
+    }
+
+    /// Wrap the index with a cache which memoizes the results of the given number of
+    /// (most recent) queries (see `QueryCache`).
+    pub fn with_query_cache(self, capacity: usize) -> QueryCache {
+        QueryCache::new(self, capacity)
+    }
+
     /// Get the reference to the inner BWT data.
     ///
     /// Note that the length of BWT is one more than the length of the actual text,
-    /// since it has a null byte to indicate empty string.
+    /// since it has a null byte to indicate empty string. If the index has been generated
+    /// using `new_with_mapping`, then this has the mapped symbols (not the original bytes).
     pub fn bwt(&self) -> &[u8] {
         &self.data
     }
 
+    /// Get the (distinct) bytes of the original data, in the order they're treated by the index.
+    ///
+    /// The index orders the bytes by their values (ascending), unless it's been generated using
+    /// `new_with_mapping`, and so the BWT ranges obtained from `suffix_range` (or `nearest`) are
+    /// ranges over the suffixes sorted in this order. Note that the null byte (used to mark the
+    /// end of the text) is excluded, unless it's a part of the data.
+    pub fn symbol_order(&self) -> Vec<u8> {
+        let length = self.data.len() as u32;
+        let unmap = self.unmap_table();
+        (0..self.occ_map.len()).filter(|&i| {
+            let next = self.occ_map.get(i + 1).cloned().unwrap_or(length);
+            let count = next - self.occ_map[i];
+            if i == 0 { count > 1 } else { count > 0 }
+        }).map(|i| unmap[i]).collect()
+    }
+
+    // Get the mapping of the symbols (held by the index) to the original bytes.
+    fn unmap_table(&self) -> Vec<u8> {
+        let mut table = (0..256).map(|i| i as u8).collect::<Vec<_>>();
+        if let Some(ref remap) = self.remap {
+            for (byte, &symbol) in remap.iter().enumerate() {
+                table[symbol as usize] = byte as u8;
+            }
+        }
+
+        table
+    }
+
     /// Generate the FM-index from the BWT data.
     ///
     /// It's not a good idea to generate FM-index from scratch all the time, especially for large inputs.
@@ -128,14 +722,33 @@ impl FMIndex {
     /// If your input doesn't change, then it's better to get the BWT data (using `bwt` method), write it
     /// to a file and generate the index from that in the future.
     pub fn new_from_bwt(bwt_data: Vec<u8>) -> FMIndex {
+        FMIndex::build(bwt_data, &AtomicBool::new(false)).unwrap()
+    }
+
+    /// Generate an FM-index for the input data, which can be cancelled (by setting the given flag)
+    /// while it's being built. This returns `None` if the build has been cancelled.
+    ///
+    /// The flag is checked between the steps of building the suffix array, and periodically
+    /// while building the tables of the index, so that interactive tools can abort the
+    /// (multi-minute) builds for large inputs.
+    pub fn new_cancellable(data: &[u8], cancel: &AtomicBool) -> Option<FMIndex> {
+        let sa = suffix_array_cancellable(data, cancel)?;
+        FMIndex::build(bwt_from_sa(data, sa), cancel)
+    }
+
+    // Build the index from BWT data, checking the flag periodically (and bailing out
+    // with `None` if it's been set).
+    fn build(bwt_data: Vec<u8>, cancel: &AtomicBool) -> Option<FMIndex> {
+        let is_cancelled = |i: usize| i & (CANCEL_CHECK_INTERVAL - 1) == 0 && cancel.load(Ordering::Relaxed);
         let mut map = Vec::new();
         let mut count = vec![0u32; bwt_data.len()];
-        let mut idx = 0;
         // generate the frequency map and forward frequency vector from BWT
-        for i in &bwt_data {
-            let value = insert(&mut map, *i);
-            count[idx] = value;
-            idx += 1;
+        for (idx, i) in bwt_data.iter().enumerate() {
+            if is_cancelled(idx) {
+                return None
+            }
+
+            count[idx] = insert(&mut map, *i);
         }
 
         generate_occurrence_index(&mut map);
@@ -144,6 +757,10 @@ impl FMIndex {
         let mut lf_occ_map = map.clone();
         // generate the LF vector (just like inverting the BWT)
         for (i, c) in bwt_data.iter().enumerate() {
+            if is_cancelled(i) {
+                return None
+            }
+
             let idx = *c as usize;
             lf_vec[i] = lf_occ_map[idx];
             lf_occ_map[idx] += 1;
@@ -156,19 +773,28 @@ impl FMIndex {
         // Only difference is that we replace the LF indices with the lengths of prefix
         // from a particular position (in other words, the number of times
         // it would take us to get to the start of string).
-        for _ in 0..(bwt_data.len() - 1) {
+        for j in 0..(bwt_data.len() - 1) {
+            if is_cancelled(j) {
+                return None
+            }
+
             let next = lf_vec[i];
             lf_vec[i] = counter;
             i = next as usize;
             counter -= 1;
         }
 
-        FMIndex {
+        Some(FMIndex {
             data: bwt_data,
             cache: count,
             occ_map: map,
-            lf_vec: lf_vec,
-        }
+            lf_vec,
+            text: None,
+            doc_starts: vec![0],
+            remap: None,
+            occ_interval: 0,
+            checkpoints: Vec::new(),
+        })
     }
 
     /// Get the nearest position of a character in the internal BWT data.
@@ -210,6 +836,12 @@ impl FMIndex {
     /// by backtracking whenever there's an invalid range.
     pub fn nearest(&self, idx: usize, ch: u8) -> usize {
         match self.occ_map.get(ch as usize) {
+            Some(res) if *res > 0 && self.occ_interval > 0 => {
+                let checkpoint = idx / self.occ_interval;
+                let start = checkpoint * self.occ_interval;
+                let count = self.checkpoints[checkpoint * self.occ_map.len() + ch as usize] as usize;
+                *res as usize + count + self.data[start..idx].iter().filter(|&&b| b == ch).count()
+            },
             Some(res) if *res > 0 => {
                 *res as usize + (0..idx).rev()
                                         .find(|&i| self.data[i] == ch)
@@ -220,55 +852,783 @@ impl FMIndex {
         }
     }
 
-    fn get_range(&self, query: &str) -> Option<(usize, usize)> {
-        let mut top = 0;
-        let mut bottom = self.data.len();
-        for ch in query.as_bytes().iter().rev() {
-            top = self.nearest(top, *ch);
-            bottom = self.nearest(bottom, *ch);
-            if top >= bottom {
-                return None
+    /// Narrow a BWT range by feeding a (leftward) byte, returning the new range (or `None` if
+    /// the range becomes invalid).
+    ///
+    /// This is a step of backward searching (with owned state). The range for the empty string
+    /// is the entire BWT (`Some((0, fm.bwt().len()))`), and folding this over the query's bytes
+    /// in the reverse gives `suffix_range`. An invalid range (`None`) remains invalid. Unlike
+    /// `nearest` (which works with the symbols in the BWT), the byte is translated if the index
+    /// has been generated using `new_with_mapping`.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    /// let fm = FMIndex::new(b"Hello, Hello, Hello" as &[u8]);
+    ///
+    /// let range = Some((0, fm.bwt().len()));
+    /// let range = b"llo".iter().rev().fold(range, |r, &ch| fm.extend_range(r, ch));
+    /// assert_eq!(range, fm.suffix_range("llo"));
+    /// assert_eq!(fm.extend_range(range, b'H'), None);
+    /// ```
+    pub fn extend_range(&self, range: Option<(usize, usize)>, ch: u8) -> Option<(usize, usize)> {
+        let ch = self.remap.as_ref().map(|r| r[ch as usize]).unwrap_or(ch);
+        range.and_then(|(top, bottom)| {
+            let (top, bottom) = (self.nearest(top, ch), self.nearest(bottom, ch));
+            if top >= bottom { None } else { Some((top, bottom)) }
+        })
+    }
+
+    // Narrow the BWT range by feeding the bytes in the order they're yielded (i.e., the
+    // iterator should yield the query's bytes from the last to the first).
+    fn get_range<I>(&self, bytes: I) -> Option<(usize, usize)>
+        where I: Iterator<Item=u8>
+    {
+        let mut range = Some((0, self.data.len()));
+        for ch in bytes {
+            range = self.extend_range(range, ch);
+            if range.is_none() {
+                break
             }
         }
 
-        if top >= bottom {
-            None
-        } else {
-            Some((top, bottom))
+        range
+    }
+
+    /// Get the range of the suffixes (in the BWT) that are prefixed by the given substring,
+    /// or `None` if the substring doesn't exist in the original data.
+    ///
+    /// Every BWT index within the range is an occurrence (see `nearest` for more details).
+    pub fn suffix_range(&self, query: &str) -> Option<(usize, usize)> {
+        self.get_range(query.bytes().rev())
+    }
+
+    // Get the position (in the original text) of the suffix at the given BWT index.
+    fn position(&self, idx: usize) -> usize {
+        // `lf_vec` holds the lengths of prefixes, which are off by one from the suffix
+        // positions (and the null byte, whose suffix is empty, has zero).
+        match self.lf_vec[idx] {
+            0 => self.data.len() - 1,
+            n => n as usize - 1,
+        }
+    }
+
+    /// Get the `(text_position, bwt_index)` pairs of all the suffixes held by the index.
+    ///
+    /// The current implementation stores the positions of all suffixes (i.e., there's no
+    /// sampling), so this is the entire suffix array - the pair at `i` is `(SA[i], i)`, which
+    /// is useful for tools that expect an explicit suffix array. Note that this includes
+    /// the empty suffix (at the BWT index of the null byte).
+    pub fn sampled_positions(&self) -> Vec<(usize, usize)> {
+        (0..self.data.len()).map(|i| (self.position(i), i)).collect()
+    }
+
+    // Get the BWT index of the suffix at the given position in the original text.
+    fn bwt_index(&self, pos: usize) -> usize {
+        let length = (pos + 1) % self.data.len();
+        self.lf_vec.iter().position(|&l| l as usize == length).expect("position out of range")
+    }
+
+    /// Extract the substring (between the given positions) from the original data.
+    ///
+    /// If the text has been retained (i.e., if the index was generated using `new_with_text`),
+    /// then this is just a slice. Otherwise, it's reconstructed from the BWT by walking through
+    /// the LF-mapping backwards from the end position, which takes O(n) time for each byte.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"Hello, world!" as &[u8]);
+    /// assert_eq!(index.extract(7, 12), b"world");
+    /// ```
+    pub fn extract(&self, start: usize, end: usize) -> Vec<u8> {
+        let length = self.data.len() - 1;
+        assert!(start <= end && end <= length,
+                "[extract] invalid range ({}..{} for length {})", start, end, length);
+        if let Some(ref text) = self.text {
+            return text[start..end].to_vec()
+        }
+
+        let mut output = vec![0; end - start];
+        if start == end {
+            return output
+        }
+
+        let unmap = self.unmap_table();
+        let mut idx = self.bwt_index(end);
+        for i in (0..output.len()).rev() {
+            let ch = self.data[idx];
+            output[i] = unmap[ch as usize];
+            idx = self.nearest(idx, ch);
+        }
+
+        output
+    }
+
+    /// Extract the reverse complement of the (DNA) substring between the given positions
+    /// (see `extract` and `reverse_complement`), which is useful for the regions on the minus strand.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"CCGATTACAGG" as &[u8]);
+    /// assert_eq!(index.extract_rc(2, 9), b"TGTAATC");
+    /// ```
+    pub fn extract_rc(&self, start: usize, end: usize) -> Vec<u8> {
+        reverse_complement(&self.extract(start, end))
+    }
+
+    // Get the original text (either the retained copy, or by inverting the BWT).
+    fn text(&self) -> Cow<'_, [u8]> {
+        match self.text {
+            Some(ref text) => Cow::Borrowed(text),
+            None => {
+                let mut text = ibwt(&self.data);
+                if self.remap.is_some() {
+                    let unmap = self.unmap_table();
+                    text.iter_mut().for_each(|b| *b = unmap[*b as usize]);
+                }
+
+                Cow::Owned(text)
+            },
+        }
+    }
+
+    // Get the positions of all the suffixes in the given range.
+    fn get_positions(&self, range: Option<(usize, usize)>) -> Vec<usize> {
+        match range {
+            Some((top, bottom)) =>  (top..bottom).map(|idx| self.position(idx)).collect(),
+            None => Vec::new(),
         }
     }
 
     /// Count the occurrences of the substring in the original data.
     pub fn count(&self, query: &str) -> usize {
-        match self.get_range(query) {
+        match self.suffix_range(query) {
             Some((top, bottom)) => bottom - top,
             None => 0,
         }
     }
 
-    /// Get the positions of occurrences of substring in the original data.
-    pub fn search(&self, query: &str) -> Vec<usize> {
-        match self.get_range(query) {
-            Some((top, bottom)) =>  (top..bottom).map(|idx| {
-                let i = self.nearest(idx, self.data[idx]);
-                self.lf_vec[i] as usize
-            }).collect(),
-            None => Vec::new(),
+    /// Count the occurrences of the substring (yielded by the iterator) in the original data.
+    ///
+    /// Backward search consumes the query from its end, so this only needs an iterator that can
+    /// be reversed. This is useful for counting queries that are produced lazily (for example,
+    /// the reverse complement of a sequence) without collecting them into a buffer.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"Hello, Hello, Hello" as &[u8]);
+    /// assert_eq!(index.count_iter(b"hello".iter().map(|b| b.to_ascii_uppercase())), 0);
+    /// assert_eq!(index.count_iter("ello".bytes()), 3);
+    /// ```
+    pub fn count_iter<I>(&self, query: I) -> usize
+        where I: DoubleEndedIterator<Item=u8>
+    {
+        match self.get_range(query.rev()) {
+            Some((top, bottom)) => bottom - top,
+            None => 0,
         }
     }
-}
-
-impl Index<usize> for FMIndex {
-    type Output = u32;
 
-    fn index(&self, i: usize) -> &u32 {
-        self.lf_vec.get(i).expect("index out of range")
+    /// Count the occurrences of the substring in the original data, up to the given cap.
+    ///
+    /// This is useful for filtering out the repetitive (high frequency) substrings. Note that
+    /// this is exact-then-min - the range can only shrink as more bytes are fed during backward
+    /// search, and so it's impossible to bail out early once it's wider than the cap. That said,
+    /// counting doesn't resolve any positions, so it's as fast as `count`.
+    pub fn count_capped(&self, query: &str, cap: usize) -> usize {
+        cmp::min(self.count(query), cap)
+    }
+
+    /// Get the positions of occurrences of substring in the original data.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        self.get_positions(self.suffix_range(query))
+    }
+
+    /// Get the positions of (at most) the given number of occurrences of substring in the
+    /// original data.
+    ///
+    /// Resolving a position walks the LF mapping (which is the expensive part of `search`), so
+    /// this bounds the work for the frequent substrings by only resolving the first few suffixes
+    /// in the range. The positions are in the same order as the ones from `search`.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"Hello, Hello, Hello" as &[u8]);
+    /// assert_eq!(index.search_limited("llo", 2), vec![16, 9]);
+    /// ```
+    pub fn search_limited(&self, query: &str, limit: usize) -> Vec<usize> {
+        self.get_positions(self.suffix_range(query).map(|(top, bottom)| {
+            (top, cmp::min(bottom, top.saturating_add(limit)))
+        }))
+    }
+
+    /// Count the distinct substrings of the given length (k-mers) in the original data.
+    ///
+    /// This walks the tree of backward search (up to the given depth) over the bytes in the
+    /// data, pruning the branches as soon as their ranges become invalid. So, it only visits the
+    /// substrings which exist in the data (up to a length of k), without resolving any positions.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"GATTACA" as &[u8]);
+    /// assert_eq!(index.distinct_kmer_count(1), 4);
+    /// assert_eq!(index.distinct_kmer_count(2), 6);   // "GA", "AT", "TT", "TA", "AC" and "CA"
+    /// ```
+    pub fn distinct_kmer_count(&self, k: usize) -> usize {
+        let symbols = self.symbol_order();
+        let mut stack = vec![(Some((0, self.data.len())), 0)];
+        let mut count = 0;
+        while let Some((range, depth)) = stack.pop() {
+            if depth == k {
+                count += 1;
+                continue
+            }
+
+            for &ch in &symbols {
+                let next = self.extend_range(range, ch);
+                if next.is_some() {
+                    stack.push((next, depth + 1));
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Get the positions of occurrences of the (DNA) substring with
+    /// [IUPAC codes](https://en.wikipedia.org/wiki/Nucleic_acid_notation), sorted in ascending order.
+    ///
+    /// Each code matches any of the bases it stands for (for example, `R` matches `A` or `G`),
+    /// and the backward search branches over only those bases. Note that the number of ranges
+    /// can grow exponentially with the number of ambiguous codes. The bytes that aren't
+    /// (uppercase) codes match themselves.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"ACGTTGCGTAACGA" as &[u8]);
+    /// assert_eq!(index.search_iupac("RCGT"), vec![0, 5]);
+    /// assert_eq!(index.search_iupac("CGN"), vec![1, 6, 11]);
+    /// ```
+    pub fn search_iupac(&self, query: &str) -> Vec<usize> {
+        let mut ranges = vec![(0, self.data.len())];
+        for ch in query.bytes().rev() {
+            let single = [ch];
+            let bases = iupac_bases(ch).unwrap_or(&single);
+            ranges = ranges.into_iter().flat_map(|range| {
+                bases.iter().filter_map(move |&b| self.extend_range(Some(range), b))
+            }).collect();
+
+            if ranges.is_empty() {
+                break
+            }
+        }
+
+        let mut positions = ranges.into_iter().flat_map(|r| self.get_positions(Some(r))).collect::<Vec<_>>();
+        positions.sort();
+        positions
+    }
+
+    /// Get the positions of occurrences of the substring along with their scores (given by
+    /// the function), sorted by the descending order of the scores.
+    ///
+    /// The function is called with each position, and it can (say) `extract` the surrounding
+    /// bytes for scoring the context. The positions with equal scores are in ascending order.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"GCGTAGGCGTCCGCGTA" as &[u8]);
+    /// // prefer the matches followed by an "A"
+    /// let ranked = index.search_ranked("GCGT", |pos| {
+    ///     if pos + 5 <= 17 && index.extract(pos + 4, pos + 5) == b"A" { 1 } else { 0 }
+    /// });
+    /// assert_eq!(ranked, vec![(0, 1), (12, 1), (6, 0)]);
+    /// ```
+    pub fn search_ranked<F>(&self, query: &str, score: F) -> Vec<(usize, i64)>
+        where F: Fn(usize) -> i64
+    {
+        let mut positions = self.search(query);
+        positions.sort();
+        let mut ranked = positions.into_iter().map(|pos| (pos, score(pos))).collect::<Vec<_>>();
+        ranked.sort_by_key(|&(_, score)| cmp::Reverse(score));
+        ranked
+    }
+
+    /// Get the positions of occurrences of the substring which are anchored to the start
+    /// and/or the end of the original data.
+    ///
+    /// This is a filter over the results of `search`, so it takes as long as `search` (even
+    /// though there can be at most one anchored match).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::{Anchor, FMIndex};
+    ///
+    /// let index = FMIndex::new(b"ATGCATGCAAAA" as &[u8]);
+    /// assert_eq!(index.search("ATG").len(), 2);
+    /// assert_eq!(index.search_anchored("ATG", Anchor::Start), vec![0]);
+    /// assert_eq!(index.search_anchored("AAA", Anchor::End), vec![9]);
+    /// assert!(index.search_anchored("AAA", Anchor::Both).is_empty());
+    /// ```
+    pub fn search_anchored(&self, query: &str, anchor: Anchor) -> Vec<usize> {
+        let length = self.data.len() - 1;
+        if query.len() > length {
+            return Vec::new()
+        }
+
+        let end = length - query.len();
+        self.search(query).into_iter().filter(|&pos| match anchor {
+            Anchor::Start => pos == 0,
+            Anchor::End => pos == end,
+            Anchor::Both => pos == 0 && pos == end,
+        }).collect()
+    }
+
+    /// Align a read against the original data, allowing a few mismatches (substitutions).
+    ///
+    /// The read is split into non-overlapping seeds of the given length, and the exact matches
+    /// of each seed are found using backward search. Every candidate alignment (implied by the
+    /// seed hits) is then verified against the original data (using `extract`), and the
+    /// positions of the alignments with at most `max_mismatches` mismatches are returned
+    /// in ascending order.
+    ///
+    /// By the pigeonhole principle, all alignments are found as long as the read has more
+    /// than `max_mismatches` seeds (i.e., `seed_len <= read.len() / (max_mismatches + 1)`).
+    /// Note that verification is faster if the index has retained the text (`new_with_text`).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"GCGTGCCCAGGGCACTGCCGCTGCAGGCGTAGGCATCGCATCACACGCGT" as &[u8]);
+    /// // one mismatch at the 5th base (actual text is "CCAGGGCACT")
+    /// assert_eq!(index.seed_and_extend(b"CCAGTGCACT", 3, 1), vec![6]);
+    /// ```
+    pub fn seed_and_extend(&self, read: &[u8], seed_len: usize, max_mismatches: usize) -> Vec<usize> {
+        assert!(seed_len > 0, "[seed_and_extend] seed length should be greater than zero");
+        let length = self.data.len() - 1;
+        let mut candidates = BTreeSet::new();
+        for (i, seed) in read.chunks(seed_len).enumerate() {
+            if seed.len() < seed_len {
+                break       // incomplete seed at the end
+            }
+
+            let offset = i * seed_len;
+            for pos in self.get_positions(self.get_range(seed.iter().rev().cloned())) {
+                if pos >= offset && pos - offset + read.len() <= length {
+                    candidates.insert(pos - offset);
+                }
+            }
+        }
+
+        candidates.into_iter().filter(|&start| {
+            let text = self.extract(start, start + read.len());
+            text.iter().zip(read).filter(|&(a, b)| a != b).count() <= max_mismatches
+        }).collect()
+    }
+
+    /// Get the `(hash, position)` pairs of the
+    /// [minimizers](https://doi.org/10.1093/bioinformatics/bth408) of the original data,
+    /// for windows of `w` consecutive k-mers.
+    ///
+    /// The minimizer of a window is its k-mer with the smallest hash (the leftmost one, in case
+    /// of a tie). The consecutive windows often share their minimizers, and so those are reported
+    /// only once (the pairs are in ascending order of positions). This slides over the text with
+    /// a monotonic queue, which takes O(nk) time for hashing the k-mers (plus the time taken
+    /// for reconstructing the text, unless it's been retained). Note that the data shorter than
+    /// a window (`w + k - 1` bytes) has no minimizers, and that this panics when `w` or `k` is zero.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"GCGTGCCCAGGGCACTGCCGCT" as &[u8]);
+    /// let minimizers = index.minimizers(4, 5);
+    /// // every window of 8 bytes has a minimizer
+    /// assert!((0..22 - 8 + 1).all(|i| minimizers.iter().any(|&(_, p)| p >= i && p + 5 <= i + 8)));
+    /// ```
+    pub fn minimizers(&self, w: usize, k: usize) -> Vec<(u64, usize)> {
+        assert!(w > 0 && k > 0, "[minimizers] window and k-mer sizes should be greater than zero");
+        let text = self.text();
+        let mut minimizers = Vec::new();
+        if text.len() < w + k - 1 {
+            return minimizers
+        }
+
+        let hashes = text.windows(k).map(kmer_hash).collect::<Vec<_>>();
+        let mut queue = VecDeque::new();       // positions with increasing hashes
+        for (i, &hash) in hashes.iter().enumerate() {
+            while queue.back().is_some_and(|&j| hashes[j] > hash) {
+                queue.pop_back();
+            }
+
+            queue.push_back(i);
+            if queue[0] + w <= i {
+                queue.pop_front();
+            }
+
+            if i + 1 >= w {
+                let pos = queue[0];
+                if minimizers.last().is_none_or(|&(_, p)| p != pos) {
+                    minimizers.push((hashes[pos], pos));
+                }
+            }
+        }
+
+        minimizers
+    }
+
+    /// Get the base composition at each offset of the motifs (of the given length) starting
+    /// at the occurrences of the seed, as the `[A, C, G, T]` counts for each offset.
+    ///
+    /// This is useful for building position weight matrices. The motifs are extracted from the
+    /// original data (using `extract`), and the occurrences whose motifs would run past the end
+    /// of the data are ignored. The bytes other than `A`, `C`, `G` and `T` (or their lowercase)
+    /// aren't counted.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"TATAATGGTATAAGCC" as &[u8]);
+    /// let profile = index.base_profile("TATAA", 7);
+    /// assert_eq!(profile[5], [0, 0, 1, 1]);   // "TATAATG" and "TATAAGC"
+    /// ```
+    pub fn base_profile(&self, seed: &str, motif_len: usize) -> Vec<[usize; 4]> {
+        let length = self.data.len() - 1;
+        let mut profile = vec![[0; 4]; motif_len];
+        for pos in self.search(seed) {
+            if pos + motif_len > length {
+                continue
+            }
+
+            for (counts, base) in profile.iter_mut().zip(self.extract(pos, pos + motif_len)) {
+                match base.to_ascii_uppercase() {
+                    b'A' => counts[0] += 1,
+                    b'C' => counts[1] += 1,
+                    b'G' => counts[2] += 1,
+                    b'T' => counts[3] += 1,
+                    _ => (),
+                }
+            }
+        }
+
+        profile
+    }
+
+    /// Find the inverted repeats (in DNA) which are at least as long as the given length.
+    ///
+    /// An inverted repeat is a sequence followed (downstream) by its reverse complement. This
+    /// returns `(pos_a, pos_b, length)` for every maximal repeat, where the reverse complement
+    /// of `text[pos_a..pos_a + length]` is `text[pos_b..pos_b + length]` and `pos_a + length <= pos_b`.
+    /// The reverse complements of all the substrings of the given length are searched in the
+    /// index, and the hits are then extended as long as they don't overlap.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"TTGCATTCAGTTTCTGAATGCTT" as &[u8]);
+    /// assert_eq!(index.inverted_repeats(5), vec![(2, 13, 8)]);
+    /// ```
+    pub fn inverted_repeats(&self, min_len: usize) -> Vec<(usize, usize, usize)> {
+        assert!(min_len > 0, "[inverted_repeats] length should be greater than zero");
+        let text = self.text();
+        let mut repeats = Vec::new();
+        if text.len() < min_len {
+            return repeats
+        }
+
+        for a in 0..(text.len() - min_len + 1) {
+            let query = reverse_complement(&text[a..a + min_len]);
+            let range = self.get_range(query.into_iter().rev());
+            for b in self.get_positions(range) {
+                if b < a + min_len {
+                    continue        // overlapping (or upstream) matches
+                }
+
+                // ignore the hits that can be extended to the left (they're part of a longer repeat)
+                if a > 0 && b + min_len < text.len() && text[a - 1] == complement(text[b + min_len]) {
+                    continue
+                }
+
+                let (mut b, mut length) = (b, min_len);
+                while a + length + 1 < b && text[a + length] == complement(text[b - 1]) {
+                    b -= 1;
+                    length += 1;
+                }
+
+                repeats.push((a, b, length));
+            }
+        }
+
+        repeats.sort();
+        repeats
+    }
+
+    /// Search the queries from a reader (one query per line), and call the given function
+    /// with each query and its positions.
+    ///
+    /// The lines are read one at a time (into a reused buffer), and so the memory consumption
+    /// remains flat regardless of the number of queries. Empty lines are skipped, and this
+    /// bails out at the first I/O error.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"Hello, Hello, Hello" as &[u8]);
+    /// let mut counts = vec![];
+    /// index.search_stream(&b"llo\nHello, \nabc\n"[..], |_, positions| counts.push(positions.len()))
+    ///      .unwrap();
+    /// assert_eq!(counts, vec![3, 2, 0]);
+    /// ```
+    pub fn search_stream<R, F>(&self, mut reader: R, mut f: F) -> io::Result<()>
+        where R: BufRead, F: FnMut(&str, Vec<usize>)
+    {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(())
+            }
+
+            let query = line.trim_end_matches(&['\n', '\r'][..]);
+            if !query.is_empty() {
+                f(query, self.search(query));
+            }
+        }
+    }
+
+    /// Get the length of the longest common prefix of the suffixes starting at the given
+    /// positions in the original data (also known as the longest common extension).
+    ///
+    /// This compares the bytes of the text directly (there's no LCP array), which takes
+    /// O(LCE) time if the text has been retained (`new_with_text`). Otherwise, the text is
+    /// reconstructed from the BWT for every query, which takes O(n) time. Note that this
+    /// panics when either of the positions is beyond the length of the text.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"GCGTAGGCGTACGCGTAG" as &[u8]);
+    /// assert_eq!(index.lce(0, 6), 5);     // "GCGTA"
+    /// assert_eq!(index.lce(0, 12), 6);    // "GCGTAG" (until the end)
+    /// ```
+    pub fn lce(&self, i: usize, j: usize) -> usize {
+        lce(&self.text(), i, j)
+    }
+
+    /// Find the tandem repeats - the (maximal) runs of a unit (at least `min_unit` bytes long)
+    /// repeated consecutively, with at least `min_copies` (complete) copies of the unit.
+    ///
+    /// This returns `(start, unit_length, copy_count)` sorted by the starts (and then the units).
+    /// The units which are themselves repeats (like "ACAC") aren't reported if their runs are
+    /// also the runs of a smaller unit (like "AC") that's long enough. Otherwise, the run is
+    /// reported with the smallest such unit that's long enough (so, `min_unit = 4` reports
+    /// "ACACACACAC" as two copies of "ACAC"). Note that this panics if `min_unit` is zero, or
+    /// if `min_copies` is less than two.
+    ///
+    /// The runs are found using LCE queries (see `lce`) over the text, which is reconstructed once
+    /// (unless it's been retained). A run of (at least two copies of) a unit "u" starts at "i" when
+    /// the LCE of "i" and "i + u" is at least "u", and the next run can't start before the end of
+    /// that extension. So, every unit takes O(n) time.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"TTACGACGACGACGTT" as &[u8]);
+    /// assert_eq!(index.tandem_repeats(2, 2), vec![(2, 3, 4)]);   // "ACG" x 4
+    /// ```
+    pub fn tandem_repeats(&self, min_unit: usize, min_copies: usize) -> Vec<(usize, usize, usize)> {
+        self.tandem_repeats_bounded(min_unit, usize::MAX, min_copies)
+    }
+
+    /// Find the tandem repeats (like `tandem_repeats`), but only with the units that are
+    /// at most `max_unit` bytes long.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"TTACGACGACGACGTTT" as &[u8]);
+    /// assert_eq!(index.tandem_repeats_bounded(1, 2, 3), vec![(14, 1, 3)]);   // "T" x 3
+    /// ```
+    pub fn tandem_repeats_bounded(&self, min_unit: usize, max_unit: usize,
+                                  min_copies: usize) -> Vec<(usize, usize, usize)> {
+        assert!(min_unit > 0, "[tandem_repeats] unit should be at least one byte long");
+        assert!(min_copies > 1, "[tandem_repeats] repeats should have at least two copies");
+        let text = self.text();
+        let mut repeats = Vec::new();
+        for unit in min_unit..(cmp::min(max_unit, text.len() / min_copies) + 1) {
+            let mut i = 0;
+            while i + unit < text.len() {
+                // the run (if any) is left-maximal, since the previous extension ended before `i`
+                let ext = lce(&text, i, i + unit);
+                if (ext + unit) / unit >= min_copies {
+                    // the smallest period of the unit (the run is also a run of that period)
+                    let period = (1..unit).find(|&d| unit.is_multiple_of(d) && text[i..i + unit - d] == text[i + d..i + unit])
+                                          .unwrap_or(unit);
+                    if unit - period < min_unit {
+                        repeats.push((i, unit, (ext + unit) / unit));
+                    }
+                }
+
+                i += ext + 1;
+            }
+        }
+
+        repeats.sort();
+        repeats
+    }
+
+    /// Get the `(document_index, offset_within_document)` pairs for the occurrences of the
+    /// substring, sorted in ascending order.
+    ///
+    /// This is meant for indexes generated using `new_multi` (other indexes have a single
+    /// document). Note that the matches spanning across documents are ignored.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new_multi(&[b"ACGTAC", b"GTTACG", b"TACGT"]);
+    /// assert_eq!(index.locate_in_documents("ACG"), vec![(0, 0), (1, 3), (2, 1)]);
+    /// ```
+    pub fn locate_in_documents(&self, query: &str) -> Vec<(usize, usize)> {
+        let length = self.data.len() - 1;
+        let mut hits = self.search(query).into_iter().filter_map(|pos| {
+            // the empty documents share their starts with the next documents
+            let doc = self.doc_starts.partition_point(|&start| start <= pos) - 1;
+            let end = self.doc_starts.get(doc + 1).cloned().unwrap_or(length);
+            if pos + query.len() > end {
+                None        // spans across documents
+            } else {
+                Some((doc, pos - self.doc_starts[doc]))
+            }
+        }).collect::<Vec<_>>();
+
+        hits.sort();
+        hits
+    }
+
+    /// Get the positions of occurrences of substring (sorted in ascending order), along with
+    /// the names of the features overlapping each of them.
+    ///
+    /// The features are `(start, end, name)` intervals (`end` being exclusive) over the original
+    /// data, and a feature overlaps a match if they share at least one position. The features are
+    /// sorted by their starts (along with the running maximum of their ends), so that each match
+    /// only goes through the features which start before its end, and stops at the first
+    /// feature beyond which none of them reach the match. The names are in the order of the
+    /// given features, and the matches which don't overlap any features have no names.
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"ACGTTGCGTAACGA" as &[u8]);
+    /// let features = [(0, 4, "gene".to_owned()), (3, 8, "exon".to_owned())];
+    /// assert_eq!(index.search_annotated("CGT", &features), vec![
+    ///     (1, vec!["gene".to_owned(), "exon".to_owned()]),
+    ///     (6, vec!["exon".to_owned()]),
+    /// ]);
+    /// ```
+    pub fn search_annotated(&self, query: &str, features: &[(usize, usize, String)]) -> Vec<(usize, Vec<String>)> {
+        let mut order = (0..features.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| features[i].0);
+        let max_ends = order.iter().scan(0, |max, &i| {
+            *max = cmp::max(*max, features[i].1);
+            Some(*max)
+        }).collect::<Vec<_>>();
+
+        let mut positions = self.search(query);
+        positions.sort();
+        positions.into_iter().map(|pos| {
+            let end = pos + query.len();
+            let count = order.partition_point(|&i| features[i].0 < end);
+            let mut hits = (0..count).rev()
+                                     .take_while(|&k| max_ends[k] > pos)
+                                     .map(|k| order[k])
+                                     .filter(|&i| features[i].1 > pos)
+                                     .collect::<Vec<_>>();
+            hits.sort();
+            (pos, hits.into_iter().map(|i| features[i].2.clone()).collect())
+        }).collect()
+    }
+
+    /// Count the (distinct) documents in which the substring occurs (see `new_multi`).
+    pub fn document_frequency(&self, query: &str) -> usize {
+        let mut docs = self.locate_in_documents(query).into_iter().map(|(doc, _)| doc).collect::<Vec<_>>();
+        docs.dedup();       // already sorted
+        docs.len()
+    }
+
+    /// Get the positions of occurrences of the reversed substring in the original data, for the
+    /// callers which already have the query in the order of backward search.
+    ///
+    /// Backward search (used by `search`) feeds the query from its last byte to its first.
+    /// This feeds the query as it is (from its first byte), so it finds the occurrences of the
+    /// reversed query, and `search_forward(query)` is the same as `search` with the reversed
+    /// query (which is only the same as `search(query)` for palindromes).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = FMIndex::new(b"GCGTGCCCAGGGCACTGCCGCTGCAGGCGTAGGCATCGCATCACACGCGT" as &[u8]);
+    /// assert_eq!(index.search_forward("TGCG"), index.search("GCGT"));
+    /// ```
+    pub fn search_forward(&self, query: &str) -> Vec<usize> {
+        self.get_positions(self.get_range(query.bytes()))
+    }
+}
+
+impl FromIterator<u8> for FMIndex {
+    /// Generate an FM-index for the bytes yielded by the iterator.
+    ///
+    /// Building the suffix array needs random access to the data, and so the bytes are
+    /// collected into a buffer before indexing (this doesn't save any memory, yet).
+    ///
+    /// ``` rust
+    /// use nucleic_acid::FMIndex;
+    ///
+    /// let index = "Hello, Hello, Hello".bytes().collect::<FMIndex>();
+    /// assert_eq!(index.count("llo"), 3);
+    /// ```
+    fn from_iter<I: IntoIterator<Item=u8>>(iter: I) -> FMIndex {
+        FMIndex::new(&iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl Index<usize> for FMIndex {
+    type Output = u32;
+
+    fn index(&self, i: usize) -> &u32 {
+        self.lf_vec.get(i).expect("index out of range")
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{FMIndex, bwt, ibwt};
+    use super::{Anchor, COMPACT_MAGIC, FMIndex, NullByteError, bwt, ibwt, kmer_hash, write_len};
+    use dna::{complement, iupac_bases, reverse_complement};
+    use std::cmp;
+    use std::collections::BTreeSet;
+    use std::io;
+    use std::iter::FromIterator;
+    use std::sync::{Arc, Barrier};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use sa::suffix_array;
+
+    const TEXT: &str = "GCGTGCCCAGGGCACTGCCGCTGCAGGCGTAGGCATCGCATCACACGCGT";
+
+    // Generate a pseudo-random text over the alphabet (with a linear congruential generator)
+    fn random_text(len: usize, mut seed: u32, alphabet: &[u8]) -> Vec<u8> {
+        (0..len).map(|_| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            alphabet[(seed >> 16) as usize % alphabet.len()]
+        }).collect()
+    }
+
+    fn random_dna(len: usize, seed: u32) -> Vec<u8> {
+        random_text(len, seed, b"ACGT")
+    }
 
     #[test]
     fn test_bwt_and_ibwt() {
@@ -282,7 +1642,7 @@ mod tests {
 
     #[test]
     fn test_fm_index() {
-        let text = String::from("GCGTGCCCAGGGCACTGCCGCTGCAGGCGTAGGCATCGCATCACACGCGT");
+        let text = String::from(TEXT);
         let index = FMIndex::new(text.as_bytes());
         assert_eq!(0, index.count("CCCCC"));
         let mut result = index.search("TG");
@@ -293,4 +1653,718 @@ mod tests {
         assert_eq!(result, vec![0, 26, 46]);
         assert_eq!(vec![1], index.search("CGTGCCC"));
     }
+
+    #[test]
+    fn test_fm_index_search_forward() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        assert_eq!(index.search_forward("CGC"), index.search("CGC"));     // palindromic
+        assert_eq!(index.search_forward("CAC").len(), 3);
+        assert_eq!(index.search_forward("TGCG"), vec![46, 26, 0]);
+        assert!(index.search_forward("GCGT").is_empty());
+        for query in &["GTGCCC", "GCGT", "CA", "TTT", "GCATCACA"] {
+            let reversed = query.chars().rev().collect::<String>();
+            assert_eq!(index.search_forward(&reversed), index.search(query));
+        }
+    }
+
+    #[test]
+    fn test_fm_index_sampled_positions() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        let samples = index.sampled_positions();
+        let sa = suffix_array(text.as_bytes());
+        assert_eq!(samples.len(), sa.len());
+        for (i, &(pos, idx)) in samples.iter().enumerate() {
+            assert_eq!(idx, i);
+            assert_eq!(pos, sa[i] as usize);
+        }
+
+        let mut result = index.search("GCGT");
+        result.sort();
+        let mut expected = samples.iter().filter(|&&(pos, _)| text[pos..].starts_with("GCGT"))
+                                         .map(|&(pos, _)| pos).collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_fm_index_extract() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        let index_with_text = FMIndex::new_with_text(text.as_bytes());
+        for &(start, end) in &[(0, 50), (0, 1), (49, 50), (10, 25), (30, 30), (50, 50)] {
+            assert_eq!(index.extract(start, end), &text.as_bytes()[start..end]);
+            assert_eq!(index.extract(start, end), index_with_text.extract(start, end));
+        }
+    }
+
+    #[test]
+    fn test_fm_index_seed_and_extend() {
+        let text = String::from("ATCTAGGAGATCTGAATCTAGTTCAACTAGCTAGATCTAGAGACAGCTAA");
+        let index = FMIndex::new(text.as_bytes());
+        // exact read
+        assert_eq!(index.seed_and_extend(b"GTTCAACTAGCT", 4, 0), vec![20]);
+        // one mismatch (the 7th base should be 'C')
+        assert_eq!(index.seed_and_extend(b"GTTCAAGTAGCT", 4, 0), Vec::<usize>::new());
+        assert_eq!(index.seed_and_extend(b"GTTCAAGTAGCT", 4, 1), vec![20]);
+        // read hanging off the end of the text
+        assert_eq!(index.seed_and_extend(b"GCTAAT", 3, 1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_fm_index_inverted_repeats() {
+        // "GCATTCAG" followed by its reverse complement "CTGAATGC" (with a loop in between)
+        let text = String::from("AAAAGCATTCAGTTTTTTCTGAATGCAAAA");
+        let index = FMIndex::new(text.as_bytes());
+        let repeats = index.inverted_repeats(6);
+        assert!(repeats.contains(&(4, 18, 8)));
+        for &(a, b, length) in &repeats {
+            assert!(length >= 6 && a + length <= b);
+            assert_eq!(reverse_complement(&text.as_bytes()[a..a + length]),
+                       &text.as_bytes()[b..b + length]);
+        }
+
+        assert!(index.inverted_repeats(30).is_empty());
+    }
+
+    #[test]
+    fn test_fm_index_count_capped() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        for query in &["G", "GC", "TG", "CCCCC", "GCGT"] {
+            for cap in 0..20 {
+                assert_eq!(index.count_capped(query, cap), cmp::min(index.count(query), cap));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fm_index_symbol_order() {
+        let text = String::from("The quick brown fox jumps over the lazy dog");
+        let index = FMIndex::new(text.as_bytes());
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.sort();
+        bytes.dedup();
+        assert_eq!(index.symbol_order(), bytes);
+        assert_eq!(FMIndex::new(b"GATTACA" as &[u8]).symbol_order(), b"ACGT");
+    }
+
+    #[test]
+    fn test_fm_index_extend_range() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        for query in &["G", "GC", "TG", "CCCCC", "GCGT", "CGTGCCC", "GCGTGCCCAGGGCACTGCCG"] {
+            let mut range = Some((0, index.bwt().len()));
+            for ch in query.bytes().rev() {
+                range = index.extend_range(range, ch);
+            }
+
+            assert_eq!(range, index.suffix_range(query));
+        }
+
+        assert_eq!(index.extend_range(None, b'G'), None);
+    }
+
+    #[test]
+    fn test_fm_index_locate_in_documents() {
+        let docs: [&[u8]; 3] = [b"GCGTGCCCAGGGCACT", b"GCCGCTGCAGGCGTAG", b"GCATCGCATCACACGCGT"];
+        let index = FMIndex::new_multi(&docs);
+        let hits = index.locate_in_documents("GC");
+        let mut expected = vec![];
+        for (i, doc) in docs.iter().enumerate() {
+            for j in 0..doc.len() - 1 {
+                if &doc[j..j + 2] == b"GC" {
+                    expected.push((i, j));
+                }
+            }
+        }
+
+        assert_eq!(hits, expected);
+        // "CTGC" exists only across the first and second documents
+        assert_eq!(index.count("CTGC"), 2);
+        assert_eq!(index.locate_in_documents("CTGC"), vec![(1, 4)]);
+        assert_eq!(index.locate_in_documents("GCGT"), vec![(0, 0), (1, 10), (2, 14)]);
+
+        let docs: [&[u8]; 6] = [b"", b"GCGTG", b"", b"", b"CGT", b""];
+        let index = FMIndex::new_multi(&docs);
+        assert_eq!(index.locate_in_documents("GCGT"), vec![(1, 0)]);
+        assert_eq!(index.locate_in_documents("CGT"), vec![(1, 1), (4, 0)]);
+        assert_eq!(index.locate_in_documents("GC"), vec![(1, 0)]);     // the other one spans the documents
+        assert_eq!(index.document_frequency("G"), 2);
+    }
+
+    #[test]
+    fn test_fm_index_new_parallel() {
+        let data = random_dna(2000, 42);
+
+        let index = FMIndex::new(&data);
+        for threads in &[1, 2, 4, 7] {
+            let parallel = FMIndex::new_parallel(&data, *threads);
+            assert_eq!(parallel.bwt(), index.bwt());
+            for query in &["A", "ACGT", "GGGA", "TTAC", "CATCAT"] {
+                assert_eq!(parallel.search(query), index.search(query));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fm_index_search_stream() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        let queries = "TG\r\nCCCCC\n\nGCGT\nCGTGCCC";
+        let mut results = vec![];
+        index.search_stream(queries.as_bytes(), |query, positions| {
+            results.push((query.to_owned(), positions));
+        }).unwrap();
+
+        assert_eq!(results.len(), 4);
+        for (query, positions) in results {
+            assert_eq!(positions, index.search(&query));
+        }
+    }
+
+    #[test]
+    fn test_fm_index_new_cancellable() {
+        let text = String::from(TEXT);
+        let cancel = AtomicBool::new(false);
+        let index = FMIndex::new_cancellable(text.as_bytes(), &cancel).unwrap();
+        assert_eq!(index.search("GCGT"), FMIndex::new(text.as_bytes()).search("GCGT"));
+
+        let data = text.as_bytes().repeat(2000);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let handle = thread::spawn(move || {
+            flag.store(true, Ordering::Relaxed);
+            FMIndex::new_cancellable(&data, &flag)
+        });
+
+        assert!(handle.join().unwrap().is_none());
+        assert!(cancel.load(Ordering::Relaxed));
+
+        // cancel from another thread while the index is being built
+        let data = random_dna(4_000_000, 3);
+
+        // the flag is set once both the threads have reached the barrier (i.e., just as the
+        // build starts), and the build checks it periodically (so, it's always cancelled)
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (flag, started) = (cancel.clone(), Arc::new(Barrier::new(2)));
+        let barrier = started.clone();
+        let handle = thread::spawn(move || {
+            barrier.wait();
+            FMIndex::new_cancellable(&data, &flag)
+        });
+
+        started.wait();
+        cancel.store(true, Ordering::Relaxed);
+        assert!(handle.join().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_fm_index_count_iter() {
+        let text = String::from(TEXT);
+        let index = FMIndex::new(text.as_bytes());
+        for query in &["TG", "CCCCC", "GCGT", "ACGC", "GCA"] {
+            let rc_iter = query.bytes().rev().map(complement);
+            let rc = String::from_utf8(reverse_complement(query.as_bytes())).unwrap();
+            assert_eq!(index.count_iter(rc_iter), index.count(&rc));
+            assert_eq!(index.count_iter(query.bytes()), index.count(query));
+        }
+    }
+
+    #[test]
+    fn test_fm_index_document_frequency() {
+        let index = FMIndex::new_multi(&[b"ACGTTACGT", b"GGGGCC", b"TTACGA", b"CGTA"]);
+        assert_eq!(index.count("ACG"), 4);     // one of them spans across documents
+        assert_eq!(index.document_frequency("ACG"), 2);
+        assert_eq!(index.document_frequency("CGT"), 2);
+        assert_eq!(index.document_frequency("GCC"), 1);
+        // "CCTTA" only spans across documents
+        assert_eq!(index.count("CCTTA"), 1);
+        assert_eq!(index.document_frequency("CCTTA"), 0);
+    }
+
+    #[test]
+    fn test_fm_index_new_with_mapping() {
+        let text = TEXT.as_bytes();
+        let mapping = [(b'T', 1), (b'G', 2), (b'C', 3), (b'A', 4)];
+        let index = FMIndex::new_with_mapping(text, &mapping);
+        let plain = FMIndex::new(text);
+        assert_eq!(index.symbol_order(), b"TGCA");
+        for query in &["GCGT", "CA", "TAGG", "ACGCGT", "AAA"] {
+            let (mut actual, mut expected) = (index.search(query), plain.search(query));
+            actual.sort();
+            expected.sort();
+            assert_eq!(actual, expected);
+        }
+
+        assert_eq!(index.extract(5, 12), &text[5..12]);
+        assert_eq!(index.seed_and_extend(b"CCAGTGCACT", 3, 1), vec![6]);
+        // the ranges follow the mapped order ("T" < "G" < "C" < "A")
+        let t = index.suffix_range("T").unwrap();
+        let a = index.suffix_range("A").unwrap();
+        assert!(t.1 <= a.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fm_index_new_with_mapping_not_one_to_one() {
+        FMIndex::new_with_mapping(b"ACGT" as &[u8], &[(b'A', 1), (b'C', 1)]);
+    }
+
+    #[test]
+    fn test_fm_index_new_frequency_ordered() {
+        let text = TEXT.as_bytes();
+        let index = FMIndex::new_frequency_ordered(text);
+        let plain = FMIndex::new(text);
+        assert_eq!(index.symbol_order(), b"CGAT");
+        for query in &["GCGT", "CA", "TAGG", "ACGCGT", "AAA", "CGC"] {
+            let (mut actual, mut expected) = (index.search(query), plain.search(query));
+            actual.sort();
+            expected.sort();
+            assert_eq!(actual, expected);
+            assert_eq!(index.count(query), plain.count(query));
+        }
+
+        assert_eq!(index.extract(0, text.len()), text);
+    }
+
+    #[test]
+    fn test_fm_index_try_new() {
+        let text = b"GCGTGCCCAGGGCACTGCCGCT" as &[u8];
+        let index = FMIndex::try_new(text).unwrap();
+        assert_eq!(index.search("GCC"), FMIndex::new(text).search("GCC"));
+        let mut text = text.to_vec();
+        text[9] = 0;
+        assert_eq!(FMIndex::try_new(&text).unwrap_err(), NullByteError { position: 9 });
+    }
+
+    #[test]
+    fn test_fm_index_search_iupac() {
+        let text = TEXT.as_bytes();
+        let index = FMIndex::new(text);
+        let naive = |query: &[u8]| (0..text.len() - query.len() + 1).filter(|&i| {
+            query.iter().zip(&text[i..]).all(|(&q, t)| iupac_bases(q).unwrap().contains(t))
+        }).collect::<Vec<_>>();
+
+        for query in &["RCGT", "GCN", "YAK", "CAT", "NNNN", "WWW", "B"] {
+            assert_eq!(index.search_iupac(query), naive(query.as_bytes()));
+        }
+
+        let mut expected = index.search("GCGT");
+        expected.extend(index.search("ACGT"));
+        expected.sort();
+        assert_eq!(index.search_iupac("RCGT"), expected);
+        assert!(index.search_iupac("TTX").is_empty());
+    }
+
+    #[test]
+    fn test_fm_index_merge() {
+        let (a, b) = (b"GCGTGCCCAGGGCACTGCCGCTGCAG" as &[u8], b"GCGTAGGCATCGCATCACACGCGT" as &[u8]);
+        let mut text = a.to_vec();
+        text.extend_from_slice(b);
+        let expected = FMIndex::new(&text);
+        let index = FMIndex::merge(FMIndex::new(a), FMIndex::new(b));
+        assert_eq!(index.bwt(), expected.bwt());
+        for query in &["GCGT", "CA", "AGGC", "CAGGCG", "TTT"] {
+            assert_eq!(index.search(query), expected.search(query));
+        }
+
+        assert_eq!(index.locate_in_documents("GCGT"), vec![(0, 0), (1, 0), (1, 20)]);
+        let index = FMIndex::merge(FMIndex::new_multi(&[a, b]), FMIndex::new_with_text(a));
+        assert_eq!(index.document_frequency("CTGC"), 2);
+        assert_eq!(index.extract(50, 60), &a[..10]);
+    }
+
+    #[test]
+    fn test_fm_index_merge_bwt() {
+        let mut pairs = vec![(b"AAAA".to_vec(), b"AAAA".to_vec()), (b"AB".repeat(5), b"ABA".to_vec()),
+                             (b"T".to_vec(), b"ACGT".to_vec()), (b"ACGT".to_vec(), b"T".to_vec())];
+        for len in 1..40 {
+            let seed = 4 * len as u32;
+            pairs.push((random_text(len, seed, b"AB"), random_text(40 - len, seed + 1, b"AB")));
+            pairs.push((random_dna(len, seed + 2), random_text(len / 2 + 1, seed + 3, b"CGT")));
+        }
+
+        for (a, b) in pairs {
+            let mut text = a.clone();
+            text.extend_from_slice(&b);
+            let index = FMIndex::merge(FMIndex::new(&a), FMIndex::new(&b));
+            assert_eq!(index.bwt(), FMIndex::new(&text).bwt());
+        }
+
+        let (a, b) = (b"GCGTGCCCAGGGCA" as &[u8], b"CTGCCGCTGCAGG" as &[u8]);
+        let mapping = [(b'T', 1), (b'G', 2), (b'C', 3), (b'A', 4)];
+        let index = FMIndex::merge(FMIndex::new_with_mapping(a, &mapping), FMIndex::new_with_text(b));
+        let expected = FMIndex::new_with_mapping(&[a, b].concat(), &mapping);
+        assert_eq!(index.bwt(), expected.bwt());
+        assert_eq!(index.search("GCAG"), expected.search("GCAG"));
+        assert_eq!(index.symbol_order(), b"TGCA");
+    }
+
+    #[test]
+    fn test_fm_index_lce() {
+        let text = TEXT.as_bytes();
+        for index in &[FMIndex::new(text), FMIndex::new_with_text(text)] {
+            for i in 0..text.len() + 1 {
+                for j in 0..text.len() + 1 {
+                    let mut expected = 0;
+                    while i + expected < text.len() && j + expected < text.len() &&
+                          text[i + expected] == text[j + expected] {
+                        expected += 1;
+                    }
+
+                    assert_eq!(index.lce(i, j), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fm_index_search_anchored() {
+        let index = FMIndex::new(b"ATGAAATGCCCATGTTTAAA" as &[u8]);
+        assert_eq!(index.search("ATG").len(), 3);
+        assert_eq!(index.search_anchored("ATG", Anchor::Start), vec![0]);
+        assert!(index.search_anchored("ATG", Anchor::End).is_empty());
+        assert_eq!(index.search("AAA").len(), 2);
+        assert_eq!(index.search_anchored("AAA", Anchor::End), vec![17]);
+        assert!(index.search_anchored("CCC", Anchor::Start).is_empty());
+        assert!(index.search_anchored("ATGTTTAAAX", Anchor::End).is_empty());
+        let index = FMIndex::new(b"GATTACA" as &[u8]);
+        assert_eq!(index.search_anchored("GATTACA", Anchor::Both), vec![0]);
+        assert!(index.search_anchored("GATTACAGATTACA", Anchor::Both).is_empty());
+    }
+
+    #[test]
+    fn test_fm_index_from_iter() {
+        let text = TEXT;
+        let index = FMIndex::from_iter(text.as_bytes().iter().cloned());
+        assert_eq!(index.search("GCGT"), vec![46, 26, 0]);
+        let index = text.bytes().map(|b| b.to_ascii_lowercase()).collect::<FMIndex>();
+        assert_eq!(index.search("gcgt"), vec![46, 26, 0]);
+        assert_eq!(index.count("GCGT"), 0);
+    }
+
+    #[test]
+    fn test_fm_index_base_profile() {
+        let motifs: [&[u8]; 5] = [b"TATAAAGC", b"TATAAGGC", b"TATAATGA", b"TATAAAGT", b"TATAA"];
+        let text = motifs.join(&b'C');
+        let index = FMIndex::new(&text);
+        let profile = index.base_profile("TATAA", 8);
+        assert_eq!(profile.len(), 8);
+        // the seed itself (the last one doesn't count, since it's at the end)
+        assert_eq!(profile[0], [0, 0, 0, 4]);
+        assert_eq!(profile[1], [4, 0, 0, 0]);
+        assert_eq!(profile[5], [2, 0, 1, 1]);
+        assert_eq!(profile[6], [0, 0, 4, 0]);
+        assert_eq!(profile[7], [1, 2, 0, 1]);
+        assert!(index.base_profile("GGGG", 4).iter().all(|c| c == &[0; 4]));
+    }
+
+    #[test]
+    fn test_fm_index_search_ranked() {
+        let text = TEXT.as_bytes();
+        let index = FMIndex::new_with_text(text);
+        let flank = |pos: usize| if pos > 0 { index.extract(pos - 1, pos)[0] } else { 0 };
+        // prefer the hits preceded by a "C", and then by an "A"
+        let ranked = index.search_ranked("GC", |pos| match flank(pos) {
+            b'C' => 2,
+            b'A' => 1,
+            _ => 0,
+        });
+
+        let mut expected = index.search("GC");
+        expected.sort_by_key(|&pos| (cmp::Reverse(match flank(pos) { b'C' => 2, b'A' => 1, _ => 0 }), pos));
+        assert_eq!(ranked.iter().map(|&(pos, _)| pos).collect::<Vec<_>>(), expected);
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(ranked[0].1, 2);
+        assert!(index.search_ranked("TTT", |_| unreachable!()).is_empty());
+    }
+
+    #[test]
+    fn test_fm_index_extract_rc() {
+        let text = TEXT.as_bytes();
+        let index = FMIndex::new(text);
+        for &(a, b) in &[(0, 10), (5, 6), (13, 50), (7, 7)] {
+            assert_eq!(index.extract_rc(a, b), reverse_complement(&index.extract(a, b)));
+            assert_eq!(reverse_complement(&index.extract_rc(a, b)), &text[a..b]);
+        }
+    }
+
+    #[test]
+    fn test_fm_index_minimizers() {
+        let text = [TEXT.as_bytes(), &[b'A'; 19]].concat();
+        let index = FMIndex::new(&text);
+        for &(w, k) in &[(1, 1), (4, 5), (10, 3), (5, 11), (60, 11), (70, 1)] {
+            let mut expected: Vec<(u64, usize)> = vec![];
+            for start in 0..(text.len() + 2 - w - k) {
+                let (mut best, mut pos) = (u64::MAX, 0);
+                for i in start..start + w {
+                    let hash = kmer_hash(&text[i..i + k]);
+                    if hash < best {
+                        best = hash;
+                        pos = i;
+                    }
+                }
+
+                if expected.last().map(|&(_, p)| p) != Some(pos) {
+                    expected.push((best, pos));
+                }
+            }
+
+            assert_eq!(index.minimizers(w, k), expected);
+        }
+
+        assert!(index.minimizers(60, 20).is_empty());
+    }
+
+    #[test]
+    fn test_fm_index_distinct_kmer_count() {
+        let text = [TEXT.as_bytes(), &[b'A'; 4]].concat();
+        let index = FMIndex::new(&text);
+        for k in 1..text.len() + 2 {
+            let expected = if k > text.len() {
+                0
+            } else {
+                text.windows(k).collect::<BTreeSet<_>>().len()
+            };
+
+            assert_eq!(index.distinct_kmer_count(k), expected);
+        }
+
+        assert_eq!(index.distinct_kmer_count(0), 1);       // the empty string
+    }
+
+    #[test]
+    fn test_fm_index_with_occ_interval() {
+        let text = [TEXT.as_bytes(), &[b'A'; 29]].concat().repeat(8);
+        let text = &text as &[u8];
+        let plain = FMIndex::new(text);
+        let dense = FMIndex::with_occ_interval(text, 4);
+        let sparse = FMIndex::with_occ_interval(text, 256);
+        for query in &["GCGT", "CA", "TAGG", "ACGCGT", "AAA", "TTT", "G", "TA"] {
+            assert_eq!(dense.search(query), plain.search(query));
+            assert_eq!(sparse.search(query), plain.search(query));
+        }
+
+        for i in 0..plain.bwt().len() + 1 {
+            for &ch in b"ACGTX" {
+                assert_eq!(dense.nearest(i, ch), plain.nearest(i, ch));
+                assert_eq!(sparse.nearest(i, ch), plain.nearest(i, ch));
+            }
+        }
+
+        assert!(dense.memory_bytes() > sparse.memory_bytes());
+        assert!(plain.memory_bytes() > sparse.memory_bytes());
+        let index = FMIndex::with_occ_interval(text, text.len() + 1);
+        assert_eq!(index.search("GCGT"), plain.search("GCGT"));
+        let merged = FMIndex::merge(sparse, plain);
+        assert_eq!(merged.search("TAGG"), FMIndex::new(&text.repeat(2)).search("TAGG"));
+        assert!(merged.memory_bytes() < FMIndex::merge(dense, FMIndex::new(text)).memory_bytes());
+    }
+
+    #[test]
+    fn test_fm_index_search_limited() {
+        let text = TEXT.as_bytes();
+        let index = FMIndex::new(text);
+        for query in &["GCGT", "CA", "G", "TTT"] {
+            let all = index.search(query);
+            for limit in 0..all.len() + 3 {
+                let positions = index.search_limited(query, limit);
+                assert_eq!(positions.len(), cmp::min(all.len(), limit));
+                assert_eq!(positions, &all[..positions.len()]);
+                assert!(positions.iter().all(|&p| text[p..].starts_with(query.as_bytes())));
+            }
+        }
+
+        assert_eq!(index.search_limited("G", usize::MAX), index.search("G"));
+    }
+
+    #[test]
+    fn test_fm_index_protein() {
+        let text = b"MKTAYIAKQRQISFVKSHFSRQLEERLGLIEVQAPILSRVGDGTQDNLSGAEKAVQVKVKALPDAQFEVVHSLAKWKRQTLGQHDFSAGEGLYTHMKALRPDEDRLSPLHSVYVDQWDWERVMGDGERQFSTLKSTVEAIWAGIKATEAAVSEEFGLAPFLPDQIHFVHSQELLSRYPDLDAKGRERAIAKDLGAVFLVGIGGKLSDGHRHDVRAPDYDDWCAEALASPEEF".repeat(4);
+        let plain = FMIndex::new(&text);
+        let index = FMIndex::new_protein(&text);
+        for query in &["KQR", "L", "GDG", "WDWE", "HHHH", "EE", "AKGRERAIAKD"] {
+            assert_eq!(index.count(query), plain.count(query));
+            let mut positions = index.search(query);
+            let mut expected = plain.search(query);
+            positions.sort();
+            expected.sort();
+            assert_eq!(positions, expected);
+        }
+
+        assert_eq!(index.symbol_order(), b"ACDEFGHIKLMNPQRSTVWY");
+        assert_eq!(index.count("B"), 0);
+        assert!(index.memory_bytes() < plain.memory_bytes());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fm_index_protein_unknown_code() {
+        FMIndex::new_protein(b"MKTAYIAKQRQISFVKSHFSRQLEERU" as &[u8]);
+    }
+
+    #[test]
+    fn test_fm_index_stats() {
+        let index = FMIndex::new(b"banana" as &[u8]);
+        assert_eq!(index.bwt(), b"annb\x00aa");
+        let stats = index.stats();
+        assert_eq!(stats.text_len, 6);
+        assert_eq!(stats.alphabet_size, 3);
+        assert_eq!(stats.bwt_runs, 5);
+        assert_eq!(stats.mean_run_len, 7.0 / 5.0);
+        assert_eq!(stats.memory_bytes, index.memory_bytes());
+
+        let stats = FMIndex::new(&b"ACGT".repeat(100)).stats();
+        assert_eq!((stats.text_len, stats.alphabet_size, stats.bwt_runs), (400, 4, 5));
+        let stats = FMIndex::new(b"A" as &[u8]).stats();
+        assert_eq!((stats.text_len, stats.alphabet_size, stats.bwt_runs), (1, 1, 2));
+    }
+
+    #[test]
+    fn test_fm_index_compact_format() {
+        let text = TEXT.as_bytes();
+        let mapping = [(b'T', 1), (b'G', 2), (b'C', 3), (b'A', 4)];
+        let docs = [&text[..20], &text[20..]];
+        let indexes = vec![FMIndex::new(text), FMIndex::new_with_text(text), FMIndex::with_occ_interval(text, 8),
+                           FMIndex::new_with_mapping(text, &mapping), FMIndex::new_multi(&docs)];
+        for index in indexes {
+            let mut bytes = Vec::new();
+            index.save_compact(&mut bytes).unwrap();
+            assert!(bytes.len() < index.memory_bytes() / 2);
+            let loaded = FMIndex::load_compact(&bytes[..]).unwrap();
+            for query in &["GCGT", "CA", "TAGG", "TTT"] {
+                assert_eq!(loaded.search(query), index.search(query));
+            }
+
+            assert_eq!(loaded.extract(3, 17), index.extract(3, 17));
+            assert_eq!(loaded.locate_in_documents("GCGT"), index.locate_in_documents("GCGT"));
+            assert_eq!(loaded.memory_bytes(), index.memory_bytes());
+            let err = FMIndex::load_compact(&bytes[..bytes.len() - 1]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+
+        let err = FMIndex::load_compact(&b"GCGTGCCCAGGGCACTGCCG"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_fm_index_compact_format_inconsistent() {
+        // write a stream (in the compact format) with the given parts
+        fn stream(data: &[u8], docs: &[usize], text: Option<&[u8]>, remap: Option<&[u8]>) -> Vec<u8> {
+            let mut bytes = COMPACT_MAGIC.to_vec();
+            write_len(&mut bytes, data.len()).unwrap();
+            bytes.extend_from_slice(data);
+            write_len(&mut bytes, 0).unwrap();
+            write_len(&mut bytes, docs.len()).unwrap();
+            for &start in docs {
+                write_len(&mut bytes, start).unwrap();
+            }
+
+            for part in &[text, remap] {
+                match *part {
+                    Some(part) => {
+                        bytes.push(1);
+                        write_len(&mut bytes, part.len()).unwrap();
+                        bytes.extend_from_slice(part);
+                    },
+                    None => bytes.push(0),
+                }
+            }
+
+            bytes
+        }
+
+        let data = bwt(b"GCGTAGGCAT");
+        let remap = (0..256).map(|b| b as u8).collect::<Vec<_>>();
+        let valid = [stream(&data, &[0], None, None), stream(&data, &[0, 4, 4, 10], None, None),
+                     stream(&data, &[0], Some(b"GCGTAGGCAT"), Some(&remap))];
+        for bytes in &valid {
+            assert_eq!(FMIndex::load_compact(&bytes[..]).unwrap().count("GC"), 2);
+        }
+
+        let invalid = [stream(&data, &[], None, None), stream(&data, &[2, 4], None, None),
+                       stream(&data, &[0, 6, 4], None, None), stream(&data, &[0, 11], None, None),
+                       stream(&data, &[0], Some(b"GCGTAGGCA"), None),
+                       stream(&data, &[0], Some(b"GCGTAGGCATT"), None),
+                       stream(&data, &[0], None, Some(&remap[..255])), stream(&data, &[0], None, Some(b""))];
+        for bytes in &invalid {
+            let err = FMIndex::load_compact(&bytes[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_fm_index_search_annotated() {
+        let text = TEXT.as_bytes();
+        let index = FMIndex::new(text);
+        let features = [(40, 50, "tail"), (0, 30, "gene"), (2, 5, "exon1"), (26, 27, "snp"),
+                        (25, 26, "before"), (10, 20, "exon2"), (30, 50, "other")];
+        let features = features.iter().map(|&(s, e, n)| (s, e, n.to_owned())).collect::<Vec<_>>();
+        for query in &["GCGT", "CA", "G", "TTT"] {
+            let hits = index.search_annotated(query, &features);
+            let mut expected = index.search(query);
+            expected.sort();
+            assert_eq!(hits.iter().map(|h| h.0).collect::<Vec<_>>(), expected);
+            for (pos, names) in hits {
+                let overlapping = features.iter().filter(|f| f.0 < pos + query.len() && pos < f.1)
+                                          .map(|f| f.2.clone())
+                                          .collect::<Vec<_>>();
+                assert_eq!(names, overlapping);
+            }
+        }
+
+        let hits = index.search_annotated("GCGT", &features);
+        let names = |v: &[&str]| v.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(hits, vec![(0, names(&["gene", "exon1"])),
+                              (26, names(&["gene", "snp"])),
+                              (46, names(&["tail", "other"]))]);
+        assert_eq!(index.search_annotated("GCGT", &[]), vec![(0, vec![]), (26, vec![]), (46, vec![])]);
+    }
+
+    #[test]
+    fn test_fm_index_tandem_repeats() {
+        let text = b"TTACGACGACGACGTTCAGCAGCAGTATATATATATAGGG" as &[u8];
+        let index = FMIndex::new(text);
+        assert_eq!(index.tandem_repeats(2, 3), vec![(2, 3, 4), (16, 3, 3), (25, 2, 6)]);
+        assert_eq!(index.tandem_repeats(1, 3), vec![(2, 3, 4), (16, 3, 3), (25, 2, 6), (37, 1, 3)]);
+        assert_eq!(index.tandem_repeats_bounded(1, 2, 3), vec![(25, 2, 6), (37, 1, 3)]);
+        assert_eq!(index.tandem_repeats(3, 4), vec![(2, 3, 4)]);
+        // the runs of the smaller units are reported with the units that are long enough
+        assert_eq!(index.tandem_repeats(4, 2), vec![(2, 6, 2), (25, 4, 3)]);
+        assert_eq!(index.tandem_repeats(5, 2), vec![(2, 6, 2), (25, 6, 2)]);
+        assert_eq!(FMIndex::new(b"ACACACACAC" as &[u8]).tandem_repeats(4, 2), vec![(0, 4, 2)]);
+
+        let brute = |text: &[u8], min_unit: usize, max_unit: usize, min_copies: usize| {
+            let mut repeats = Vec::new();
+            for unit in min_unit..cmp::min(max_unit + 1, text.len()) {
+                for start in 0..(text.len() + 1).saturating_sub(unit) {
+                    let copies = (1..).take_while(|c| {
+                        start + (c + 1) * unit <= text.len() &&
+                            text[start + c * unit..start + (c + 1) * unit] == text[start..start + unit]
+                    }).count() + 1;
+                    let extends = start > 0 && text[start - 1 + unit] == text[start - 1];
+                    let period = (1..unit + 1).find(|&d| unit.is_multiple_of(d) &&
+                                                         text[start..start + unit - d] == text[start + d..start + unit]);
+                    if copies >= min_copies && !extends && unit - period.unwrap() < min_unit {
+                        repeats.push((start, unit, copies));
+                    }
+                }
+            }
+
+            repeats.sort();
+            repeats
+        };
+
+        let random = random_text(300, 11, b"AC");
+
+        let repetitive = [b"AC".repeat(20), b"ACA".repeat(15), [b"A".repeat(9), b"CAAC".repeat(7)].concat()];
+        for text in [text, &random].iter().cloned().chain(repetitive.iter().map(|t| &t[..])) {
+            let index = FMIndex::new(text);
+            for &(min_unit, max_unit, copies) in &[(1, 50, 2), (2, 50, 2), (1, 8, 3), (3, 6, 2)] {
+                assert_eq!(index.tandem_repeats_bounded(min_unit, max_unit, copies), brute(text, min_unit, max_unit, copies));
+            }
+        }
+    }
 }
@@ -0,0 +1,179 @@
+use bwt::FMIndex;
+
+use std::collections::{HashMap, VecDeque};
+
+// The cached results of a query
+#[derive(Clone, Debug)]
+struct Entry {
+    count: usize,
+    /// positions (if they've been asked for)
+    positions: Option<Vec<usize>>,
+}
+
+/// An FM-index which memoizes the results of `count` and `search` for the full query strings,
+/// evicting the least recently used query once the cache is full.
+///
+/// This helps the workloads which repeat the exact same queries (like fixed adapter sequences).
+/// The counts are cached separately from the positions, so `count` never resolves the positions
+/// (and a `search` following the `count` of a query is still a miss). Note that the cached
+/// positions are in the same order as the ones from `FMIndex::search`.
+///
+/// ``` rust
+/// use nucleic_acid::FMIndex;
+///
+/// let mut index = FMIndex::new(b"Hello, Hello, Hello" as &[u8]).with_query_cache(16);
+/// assert_eq!(index.search("llo"), vec![16, 9, 2]);
+/// assert_eq!(index.count("llo"), 3);
+/// assert_eq!((index.hits(), index.misses()), (1, 1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct QueryCache {
+    index: FMIndex,
+    capacity: usize,
+    /// cached results of the queries
+    entries: HashMap<String, Entry>,
+    /// queries in the order of their use (least recent first)
+    order: VecDeque<String>,
+    hits: usize,
+    misses: usize,
+}
+
+impl QueryCache {
+    /// Wrap the index with a cache which can hold the results of the given number of queries.
+    pub fn new(index: FMIndex, capacity: usize) -> QueryCache {
+        QueryCache {
+            index,
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Get the reference to the inner index.
+    pub fn index(&self) -> &FMIndex {
+        &self.index
+    }
+
+    /// Get the inner index back (discarding the cache).
+    pub fn into_inner(self) -> FMIndex {
+        self.index
+    }
+
+    /// Get the number of queries which have been answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Get the number of queries which have been resolved using the index.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    // Get the cached entry of the query (if any), marking it as the most recently used one.
+    fn touch(&mut self, query: &str) -> Option<&mut Entry> {
+        if self.entries.contains_key(query) {
+            let idx = self.order.iter().position(|q| q == query).unwrap();
+            let query = self.order.remove(idx).unwrap();
+            self.order.push_back(query);
+        }
+
+        self.entries.get_mut(query)
+    }
+
+    // Cache the entry of a query, evicting the least recently used query if the cache is full
+    // (the cache should have non-zero capacity).
+    fn insert(&mut self, query: &str, entry: Entry) {
+        if self.entries.len() >= self.capacity {
+            let oldest = self.order.pop_front().unwrap();
+            self.entries.remove(&oldest);
+        }
+
+        self.entries.insert(query.to_owned(), entry);
+        self.order.push_back(query.to_owned());
+    }
+
+    /// Count the occurrences of the substring in the original data (see `FMIndex::count`).
+    pub fn count(&mut self, query: &str) -> usize {
+        if self.capacity == 0 {
+            self.misses += 1;
+            return self.index.count(query)
+        }
+
+        if let Some(count) = self.touch(query).map(|e| e.count) {
+            self.hits += 1;
+            return count
+        }
+
+        self.misses += 1;
+        let count = self.index.count(query);
+        self.insert(query, Entry { count, positions: None });
+        count
+    }
+
+    /// Get the positions of occurrences of substring in the original data (see `FMIndex::search`).
+    pub fn search(&mut self, query: &str) -> Vec<usize> {
+        if self.capacity == 0 {
+            self.misses += 1;
+            return self.index.search(query)
+        }
+
+        match self.touch(query).map(|e| e.positions.clone()) {
+            Some(Some(positions)) => {
+                self.hits += 1;
+                positions
+            },
+            Some(None) => {     // only the count has been cached
+                self.misses += 1;
+                let positions = self.index.search(query);
+                self.entries.get_mut(query).unwrap().positions = Some(positions.clone());
+                positions
+            },
+            None => {
+                self.misses += 1;
+                let positions = self.index.search(query);
+                self.insert(query, Entry { count: positions.len(), positions: Some(positions.clone()) });
+                positions
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bwt::FMIndex;
+
+    #[test]
+    fn test_query_cache() {
+        let text = b"GCGTGCCCAGGGCACTGCCGCTGCAGGCGTAGGCATCGCATCACACGCGT" as &[u8];
+        let plain = FMIndex::new(text);
+        let mut index = FMIndex::new(text).with_query_cache(2);
+        for _ in 0..10 {
+            assert_eq!(index.search("GCGT"), plain.search("GCGT"));
+            assert_eq!(index.count("GCGT"), 3);
+        }
+
+        assert_eq!((index.hits(), index.misses()), (19, 1));
+        assert_eq!(index.count("CA"), plain.count("CA"));
+        index.count("GCGT");                                // "CA" is now the least recent query
+        assert_eq!(index.count("TTT"), 0);                  // which gets evicted
+        assert_eq!((index.hits(), index.misses()), (20, 3));
+        index.count("GCGT");
+        index.count("CA");
+        assert_eq!((index.hits(), index.misses()), (21, 4));
+
+        // counting doesn't resolve the positions
+        assert_eq!(index.count("CA"), plain.count("CA"));
+        assert!(index.entries["CA"].positions.is_none());
+        assert_eq!(index.search("CA"), plain.search("CA"));
+        assert_eq!(index.search("CA"), plain.search("CA"));
+        assert_eq!(index.count("CA"), plain.count("CA"));
+        assert_eq!((index.hits(), index.misses()), (24, 5));
+
+        let mut index = index.into_inner().with_query_cache(0);
+        assert_eq!(index.search("CA"), plain.search("CA"));
+        assert_eq!(index.count("CA"), plain.count("CA"));
+        assert_eq!((index.hits(), index.misses()), (0, 2));
+    }
+}
@@ -0,0 +1,163 @@
+use bits_vec::{BitsVec, ReprUsize};
+
+/// A table of records with fixed-width (unsigned) fields, holding each field in its own
+/// `BitsVec` (a column).
+///
+/// This is useful for storing the structured records compactly - for example, a SNP (with
+/// the delta of its position, the reference and alternate bases, and its quality) needs only
+/// a few bytes with the right widths, instead of a struct of words.
+///
+/// ``` rust
+/// use nucleic_acid::PackedColumns;
+///
+/// // delta, ref, alt, quality
+/// let mut snps = PackedColumns::new(&[20, 2, 2, 6]);
+/// snps.push(&[1042, 0, 2, 37]);
+/// snps.push(&[315, 3, 1, 60]);
+/// assert_eq!(snps.get(1), vec![315, 3, 1, 60]);
+/// assert_eq!(snps.get_field::<u8>(0, 3), 37);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedColumns {
+    columns: Vec<BitsVec<usize>>,
+    units: usize,
+}
+
+impl PackedColumns {
+    /// Create an empty table with fields of the given widths (bits). Note that this panics
+    /// if there aren't any fields, or if any of the widths can't be held by a `BitsVec`.
+    pub fn new(widths: &[usize]) -> PackedColumns {
+        assert!(!widths.is_empty(), "[new] records should have at least one field");
+        PackedColumns {
+            columns: widths.iter().map(|&bits| BitsVec::new(bits)).collect(),
+            units: 0,
+        }
+    }
+
+    /// Returns the number of records in the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.units
+    }
+
+    /// Checks whether the table has any records.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.units == 0
+    }
+
+    /// Returns the number of fields in each record.
+    #[inline]
+    pub fn fields(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Returns the widths (bits) of the fields.
+    pub fn widths(&self) -> Vec<usize> {
+        self.columns.iter().map(|c| c.bits()).collect()
+    }
+
+    /// Get the column (the values of all the records) for the given field. Note that this
+    /// panics when the field is out of bounds.
+    pub fn column(&self, field: usize) -> &BitsVec<usize> {
+        self.check_field("column", field);
+        &self.columns[field]
+    }
+
+    // Panic if the field is out of bounds.
+    fn check_field(&self, op: &str, field: usize) {
+        assert!(field < self.columns.len(), "[{}] field out of bounds ({} >= {})", op, field, self.columns.len());
+    }
+
+    /// Push a record (with a value for each of the fields) into the table. Note that this
+    /// panics (without pushing) if the record doesn't have all the fields, or if any of the
+    /// values is larger than what its field can hold.
+    pub fn push(&mut self, record: &[usize]) {
+        assert!(record.len() == self.columns.len(),
+                "[push] record should have {} fields (got {})", self.columns.len(), record.len());
+        for (field, (column, &value)) in self.columns.iter().zip(record).enumerate() {
+            assert!(value >> column.bits() == 0,
+                    "[push] value of field {} needs more than {} bits ({})", field, column.bits(), value);
+        }
+
+        for (column, &value) in self.columns.iter_mut().zip(record) {
+            column.push(value);
+        }
+
+        self.units += 1;
+    }
+
+    /// Get the record at an index (the values of all its fields). Note that this panics when
+    /// the index is out of bounds.
+    pub fn get(&self, i: usize) -> Vec<usize> {
+        assert!(i < self.units, "[get] index out of bounds ({} >= {})", i, self.units);
+        self.columns.iter().map(|c| c.get(i)).collect()
+    }
+
+    /// Get the value of a field in the record at an index, converted to the given type. Note
+    /// that this panics when either the index or the field is out of bounds, or when the value
+    /// isn't valid for the type (like a surrogate for `char`).
+    pub fn get_field<T: ReprUsize>(&self, i: usize, field: usize) -> T {
+        self.check_field("get_field", field);
+        T::from_usize(self.columns[field].get(i))
+    }
+
+    /// Set the value of a field in the record at an index. Note that this panics when either
+    /// the index or the field is out of bounds, or if the value can't be held by the field.
+    pub fn set_field<T: ReprUsize>(&mut self, i: usize, field: usize, value: T) {
+        self.check_field("set_field", field);
+        self.columns[field].set(i, value.into_usize());
+    }
+
+    /// Creates an iterator over the records of the table.
+    pub fn iter(&self) -> impl Iterator<Item=Vec<usize>> + '_ {
+        (0..self.units).map(move |i| self.get(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PackedColumns;
+
+    #[test]
+    fn test_packed_columns() {
+        let mut table = PackedColumns::new(&[20, 2, 2, 6]);
+        assert_eq!((table.fields(), table.widths()), (4, vec![20, 2, 2, 6]));
+        let records = (0..100).map(|i| vec![i * 7919 % (1 << 20), i % 4, (i + 1) % 4, i % 61])
+                              .collect::<Vec<_>>();
+        for record in &records {
+            table.push(record);
+        }
+
+        assert_eq!(table.len(), 100);
+        assert_eq!(table.iter().collect::<Vec<_>>(), records);
+        assert_eq!(table.get_field::<u32>(42, 0), (42 * 7919) as u32);
+        assert!(table.get_field::<bool>(2, 1));
+        table.set_field(42, 3, 63u8);
+        assert_eq!(table.get(42), vec![42 * 7919, 2, 3, 63]);
+        assert_eq!(table.column(1).iter().take(5).collect::<Vec<_>>(), vec![0, 1, 2, 3, 0]);
+        assert_eq!(table.column(2).inner_len(), 100 * 2 / 64 + 1);
+    }
+
+    #[test]
+    fn test_packed_columns_char_field() {
+        let mut table = PackedColumns::new(&[21, 16]);
+        table.push(&['λ' as usize, 0xd800]);
+        assert_eq!(table.get_field::<char>(0, 0), 'λ');
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_packed_columns_invalid_char_field() {
+        let mut table = PackedColumns::new(&[21, 16]);
+        table.push(&['λ' as usize, 0xd800]);
+        table.get_field::<char>(0, 1);      // surrogate
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_packed_columns_value_too_large() {
+        let mut table = PackedColumns::new(&[20, 2]);
+        table.push(&[1, 4]);
+    }
+}
@@ -0,0 +1,64 @@
+// Get the complement of a nucleotide (the case is preserved, and non-nucleotide bytes are
+// left as they are).
+pub fn complement(base: u8) -> u8 {
+    match base {
+        b'A' => b'T',
+        b'T' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'a' => b't',
+        b't' => b'a',
+        b'c' => b'g',
+        b'g' => b'c',
+        _ => base,
+    }
+}
+
+// Get the bases allowed by an IUPAC (nucleotide) code, or `None` if the byte isn't a
+// code (only the uppercase codes are recognized).
+pub fn iupac_bases(code: u8) -> Option<&'static [u8]> {
+    let bases: &'static [u8] = match code {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' | b'U' => b"T",
+        b'R' => b"AG",
+        b'Y' => b"CT",
+        b'S' => b"CG",
+        b'W' => b"AT",
+        b'K' => b"GT",
+        b'M' => b"AC",
+        b'B' => b"CGT",
+        b'D' => b"AGT",
+        b'H' => b"ACT",
+        b'V' => b"ACG",
+        b'N' => b"ACGT",
+        _ => return None,
+    };
+
+    Some(bases)
+}
+
+/// Get the reverse complement of a DNA sequence.
+///
+/// ``` rust
+/// assert_eq!(nucleic_acid::reverse_complement(b"GATTACA"), b"TGTAATC");
+/// ```
+///
+/// Note that only the `A`, `T`, `C` and `G` (or their lowercase) bytes are complemented. Others
+/// (like `N`) are simply reversed.
+pub fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().map(|&b| complement(b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reverse_complement;
+
+    #[test]
+    fn test_reverse_complement() {
+        assert_eq!(reverse_complement(b"ACGTNacgt"), b"acgtNACGT");
+        assert_eq!(reverse_complement(&reverse_complement(b"GATTACA")), b"GATTACA");
+        assert!(reverse_complement(b"").is_empty());
+    }
+}
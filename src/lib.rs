@@ -2,11 +2,24 @@
        html_favicon_url = "https://www.rust-lang.org/favicon.ico", html_root_url = ".")]
 extern crate bit_vec;
 extern crate num_traits;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 mod bits_vec;
 mod bwt;
+mod cache;
+mod columns;
+mod dna;
 mod sa;
 
-pub use bwt::{bwt, ibwt, FMIndex};
-pub use bits_vec::{BitsVec, ReprUsize};
+pub use bwt::{bwt, ibwt, Anchor, FMIndex, IndexStats, NullByteError};
+pub use bits_vec::{BitsVec, BitsVecBuilder, BitsVecView, DeltaBuilder, LenError, MonotonicError, PushError,
+                    ReprUsize, VarintError};
+pub use cache::QueryCache;
+pub use columns::PackedColumns;
+pub use dna::reverse_complement;
 pub use sa::suffix_array;
@@ -1,10 +1,18 @@
 use bit_vec::BitVec;
 use num_traits::{Num, NumCast, cast};
 
+use std::cmp;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
 // Prefer this for marking, instead of Option<u32> (as it requires additional byte of memory)
 // We could use usize here, but it will consume a great deal of memory. Keeping that aside, even
 // the size of the giant human genome is only 70% of this value (~3 billion bases). So, we're good...
-use std::u32::MAX as MARKER;
+const MARKER: u32 = u32::MAX;
+
+// Number of rows between the occurrence checkpoints, while merging the sorted blocks
+const MERGE_OCC_INTERVAL: usize = 64;
 
 fn induced_sort_large<T>(input: &[T], approx_sa: &mut [u32],
                          mut bucket_heads: Vec<u32>, type_map: &BitVec)
@@ -104,6 +112,14 @@ pub fn insert<T>(vec: &mut Vec<u32>, value: T) -> u32
 /// ```
 pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
     where T: Num + NumCast + PartialOrd + Copy
+{
+    suffix_array_cancellable(input, &AtomicBool::new(false)).unwrap()
+}
+
+// Generates the suffix array, checking the flag between the steps (and bailing out
+// with `None` if it's been set).
+pub fn suffix_array_cancellable<T>(input: &[T], cancel: &AtomicBool) -> Option<Vec<u32>>
+    where T: Num + NumCast + PartialOrd + Copy
 {
     let length = input.len();
     let length_32 = length as u32;
@@ -140,6 +156,10 @@ pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
     let mut bucket_tails = vec![0u32; max_byte + 1];
     let mut bucket_heads = vec![0u32; max_byte + 1];
 
+    if cancel.load(Ordering::Relaxed) {
+        return None
+    }
+
     // 2. Fill the bucket heads and tails (heads for L-types and tails for S-types)
     let mut j = 0;
     for i in 0..(max_byte + 1) {
@@ -155,6 +175,10 @@ pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
     drop(bytes);
     drop(bucket_sizes);
 
+    if cancel.load(Ordering::Relaxed) {
+        return None
+    }
+
     // 3. Build the approximate SA for initial induced sorting
     let mut approx_sa = {
         let mut vec = vec![MARKER; length + 1];
@@ -174,9 +198,17 @@ pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
         vec
     };
 
+    if cancel.load(Ordering::Relaxed) {
+        return None
+    }
+
     // 4. Induced sort with respect to L & S types (using the buckets)
-    induced_sort_large(&input, &mut approx_sa, bucket_heads.clone(), &type_map);
-    induced_sort_small(&input, &mut approx_sa, bucket_tails.clone(), &type_map);
+    induced_sort_large(input, &mut approx_sa, bucket_heads.clone(), &type_map);
+    induced_sort_small(input, &mut approx_sa, bucket_tails.clone(), &type_map);
+
+    if cancel.load(Ordering::Relaxed) {
+        return None
+    }
 
     // 5. Record the indices that share LMS substrings
     let mut label = 0;
@@ -193,7 +225,7 @@ pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
                 continue
             }
 
-            if !is_equal_lms(&input, &lms_map, last_idx, idx) {
+            if !is_equal_lms(input, &lms_map, last_idx, idx) {
                 label += 1;
             }
 
@@ -221,11 +253,15 @@ pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
     let summary_len = summary_index_idx.len() as u32;
     drop(lms_bytes);
 
+    if cancel.load(Ordering::Relaxed) {
+        return None
+    }
+
     // 6. Build the final SA
     let mut final_sa = {
         let summary_sa = if label + 1 < summary_len {
             // recursion (we don't have enough labels - multiple LMS substrings are same)
-            let array = suffix_array(&summary_index_val);
+            let array = suffix_array_cancellable(&summary_index_val, cancel)?;
             drop(summary_index_val);
             array
         } else {
@@ -256,15 +292,199 @@ pub fn suffix_array<T>(input: &[T]) -> Vec<u32>
     };
 
     // ... and sort it one last time
-    induced_sort_large(&input, &mut final_sa, bucket_heads, &type_map);
-    induced_sort_small(&input, &mut final_sa, bucket_tails, &type_map);
+    induced_sort_large(input, &mut final_sa, bucket_heads, &type_map);
+    induced_sort_small(input, &mut final_sa, bucket_tails, &type_map);
+
+    Some(final_sa)      // peek of memory consumption
+}
+
+// Get the bits for the positions in the range (which should be before `end`), which are set
+// when the suffix at the position is greater than the suffix at `end`.
+//
+// The longest common prefixes (with the suffix at `end`) are found like the Z-algorithm, using
+// the Z-values of the suffix at `end` (for the shifts up to the length of the range). So, this
+// takes linear time in the length of the range (and the longest common prefix).
+fn greater_than_suffix(input: &[u8], range: Range<usize>, end: usize) -> BitVec {
+    let pattern = &input[end..];
+    let mut z = vec![0; cmp::min(range.len(), pattern.len())];
+    let (mut left, mut right) = (0, 0);     // pattern[left..right] is a prefix of the pattern
+    for k in 1..z.len() {
+        let mut len = if k < right { cmp::min(z[k - left], right - k) } else { 0 };
+        if k + len >= right {
+            while k + len < pattern.len() && pattern[len] == pattern[k + len] {
+                len += 1;
+            }
+
+            left = k;
+            right = k + len;
+        }
+
+        z[k] = len;
+    }
+
+    let mut bits = BitVec::from_elem(range.len(), false);
+    let (mut left, mut right) = (0, 0);     // input[left..right] is a prefix of the pattern
+    for p in range.clone() {
+        let mut len = if p < right { cmp::min(z.get(p - left).cloned().unwrap_or(0), right - p) } else { 0 };
+        if p + len >= right {
+            while len < pattern.len() && input[p + len] == pattern[len] {
+                len += 1;
+            }
+
+            left = p;
+            right = p + len;
+        }
+
+        // the pattern is shorter, so it's smaller if it's a prefix
+        bits.set(p - range.start, len == pattern.len() || input[p + len] > pattern[len]);
+    }
+
+    bits
+}
+
+// Sort the suffixes (of the entire input) starting in the given block.
+//
+// The suffixes of the block (truncated at its end) are sorted by the induced sorting method,
+// where every byte is paired with a bit telling whether the suffix following it is greater than
+// the suffix at the end of the block (the bit is set for the last byte). When a truncated suffix
+// is a prefix of another, the suffix after the end of the other one (at the same offset) decides
+// their order, and the bit at that offset breaks the tie. The bits never disagree with the order
+// elsewhere, since the suffixes following the bytes are compared with the same suffix.
+fn block_suffix_array(input: &[u8], start: usize, end: usize) -> Vec<u32> {
+    let greater = greater_than_suffix(input, (start + 1)..end, end);
+    let symbols = (start..end).map(|p| {
+        let bit = p + 1 == end || greater.get(p - start).unwrap();
+        2 * input[p] as u16 + bit as u16
+    }).collect::<Vec<_>>();
+
+    suffix_array(&symbols).into_iter().skip(1).map(|i| i + start as u32).collect()
+}
+
+// Merge the (sorted) suffixes of two adjacent blocks.
+//
+// The suffixes of the left block are ranked among the suffixes of the right block by backward
+// search (like the LF-mapping of an FM-index), from the end of the left block. Every suffix
+// of the right block is preceded by a byte of the same block (which makes its BWT), except the
+// first one, and the last suffix is followed by a suffix beyond the block, which is compared
+// (to the suffixes of the left block) like `block_suffix_array`.
+fn merge_blocks(input: &[u8], (start, mid, end): (usize, usize, usize),
+                left: Vec<u32>, right: Vec<u32>) -> Vec<u32> {
+    let first_row = right.iter().position(|&i| i as usize == mid).unwrap();
+    let bwt = right.iter().map(|&i| input[(i as usize).saturating_sub(1)]).collect::<Vec<_>>();
+    let mut less = vec![0u32; 257];
+    for &i in &right {
+        less[input[i as usize] as usize + 1] += 1;
+    }
+
+    for i in 1..less.len() {
+        less[i] += less[i - 1];
+    }
+
+    // occurrences of the bytes before the checkpoints (of the bytes in the BWT)
+    let mut symbols = vec![None; 256];
+    for &b in &bwt {
+        if symbols[b as usize].is_none() {
+            symbols[b as usize] = Some(symbols.iter().flatten().count());
+        }
+    }
+
+    let alphabet = symbols.iter().flatten().count();
+    let mut counts = vec![0u32; alphabet];
+    let mut checkpoints = Vec::with_capacity((bwt.len() / MERGE_OCC_INTERVAL + 1) * alphabet);
+    for (row, &b) in bwt.iter().enumerate() {
+        if row % MERGE_OCC_INTERVAL == 0 {
+            checkpoints.extend_from_slice(&counts);
+        }
+
+        if row != first_row {
+            counts[symbols[b as usize].unwrap()] += 1;
+        }
+    }
+
+    checkpoints.extend_from_slice(&counts);
+    let occ = |b: u8, row: usize| match symbols[b as usize] {
+        Some(symbol) => {
+            let checkpoint = row / MERGE_OCC_INTERVAL;
+            let from = checkpoint * MERGE_OCC_INTERVAL;
+            let extra = (from..row).filter(|&r| r != first_row && bwt[r] == b).count();
+            checkpoints[checkpoint * alphabet + symbol] as usize + extra
+        },
+        None => 0,
+    };
+
+    let last = input[end - 1];
+    let greater = greater_than_suffix(input, (start + 1)..(mid + 1), end);
+    let mut ranks = vec![0; mid - start];
+    let mut rank = first_row;
+    for i in (start..mid).rev() {
+        let b = input[i];
+        rank = less[b as usize] as usize + occ(b, rank);
+        if b == last && greater.get(i - start).unwrap() {
+            rank += 1;      // the last suffix of the right block (followed by the one at `end`)
+        }
+
+        ranks[i - start] = rank;
+    }
 
-    final_sa        // peek of memory consumption
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut row = 0;
+    for i in left {
+        let rank = ranks[i as usize - start];
+        merged.extend_from_slice(&right[row..rank]);
+        merged.push(i);
+        row = rank;
+    }
+
+    merged.extend_from_slice(&right[row..]);
+    merged
+}
+
+// Generates the suffix array using multiple threads. The input is split into blocks (one for
+// each thread), and the suffixes starting in every block are sorted (see `block_suffix_array`)
+// in parallel. The sorted blocks are then merged (see `merge_blocks`) in pairs, where the pairs
+// of each round are merged in parallel.
+//
+// The blocks take linear time, but the merges need O(nlogp) backward search steps (for "p"
+// threads), where the last round has to rank half of the suffixes with a single thread.
+pub fn suffix_array_parallel(input: &[u8], threads: usize) -> Vec<u32> {
+    let length = input.len();
+    let blocks = cmp::max(cmp::min(threads, length), 1);
+    let bounds = (0..(blocks + 1)).map(|i| i * length / blocks).collect::<Vec<_>>();
+    let mut sorted = thread::scope(|scope| {
+        let handles = bounds.windows(2).map(|w| {
+            let (start, end) = (w[0], w[1]);
+            scope.spawn(move || (start, end, block_suffix_array(input, start, end)))
+        }).collect::<Vec<_>>();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    });
+
+    while sorted.len() > 1 {
+        sorted = thread::scope(|scope| {
+            let mut handles = Vec::with_capacity(sorted.len() / 2 + 1);
+            let mut blocks = sorted.drain(..);
+            while let Some((start, mid, left)) = blocks.next() {
+                match blocks.next() {
+                    Some((_, end, right)) => handles.push(scope.spawn(move || {
+                        (start, end, merge_blocks(input, (start, mid, end), left, right))
+                    })),
+                    None => handles.push(scope.spawn(move || (start, mid, left))),
+                }
+            }
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+    }
+
+    let mut sa = Vec::with_capacity(length + 1);
+    sa.push(length as u32);     // null byte
+    sa.extend(sorted.pop().map(|(_, _, sa)| sa).unwrap_or_default());
+    sa
 }
 
 #[cfg(test)]
 mod tests {
-    use super::suffix_array;
+    use super::{greater_than_suffix, suffix_array, suffix_array_parallel};
 
     #[test]
     fn test_suffix_array() {
@@ -277,4 +497,45 @@ mod tests {
         assert_eq!(sa.into_iter().skip(1).map(|i| &text[i as usize..]).collect::<Vec<_>>(),
                    rotations);
     }
+
+    #[test]
+    fn test_suffix_array_parallel() {
+        let text = b"ATCGAATCGAGAGATCATCGAATCGAGATCATCGAAATCATCGAATCGTC";
+        for threads in 1..8 {
+            assert_eq!(suffix_array_parallel(text, threads), suffix_array(text as &[u8]));
+        }
+
+        let repetitive = [&[b'A'; 300] as &[u8], &b"ACG".repeat(100), &text.repeat(8), b"A"];
+        for text in &repetitive {
+            for threads in &[1, 3, 8] {
+                assert_eq!(suffix_array_parallel(text, *threads), suffix_array(text));
+            }
+        }
+
+        // the blocks (and the merges) are decided by the suffixes beyond them
+        let mut seed = 7u32;
+        for len in (1..300).step_by(23) {
+            for &alphabet in &[1, 2, 4] {
+                let text = (0..len).map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    b"ACGT"[(seed >> 16) as usize % alphabet]
+                }).collect::<Vec<_>>();
+                let periodic = [&text[..len / 3 + 1], &text[..len / 3 + 1], &[b'T'; 10][..], &text].concat();
+                for text in &[text, periodic] {
+                    for threads in &[2, 5, 300] {
+                        assert_eq!(suffix_array_parallel(text, *threads), suffix_array(&text[..]));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_greater_than_suffix() {
+        let text = b"ACACACAGACACACACTACACACATT";
+        for end in 1..text.len() + 1 {
+            let bits = greater_than_suffix(text, 0..end, end);
+            assert!((0..end).all(|p| bits.get(p).unwrap() == (text[p..] > text[end..])));
+        }
+    }
 }